@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use macroquad::math::Vec2;
+
+use crate::{controls::Action, math, player::{Player, PlayerInput, PIXELS_PER_METER}, world::World};
+
+// how close to the target heading the AI considers itself on course, in rad
+const HEADING_TOLERANCE: f32 = 0.05;
+// how far off the nose the AI is willing to fire its gun, in rad
+const GUN_TOLERANCE: f32 = 0.1;
+// seconds between missile launches
+const MISSILE_INTERVAL: f32 = 4.0;
+const CRUISE_THROTTLE: f32 = 80.0;
+
+// an enemy pointing within this angle of us and closer than the range is a threat
+const THREAT_ANGLE: f32 = 0.35;
+const THREAT_RANGE: f32 = 600.0; // in pixels
+// below this multiple of the stall speed the AI extends to regain energy
+const EXTEND_SPEED: f32 = 1.5;
+// and goes back to fighting once it is back above this multiple of its corner speed
+const RECOVERED_SPEED: f32 = 1.3;
+// closer than this and faster than the target the AI brakes to avoid overshooting
+const OVERSHOOT_RANGE: f32 = 180.0; // in pixels
+const OVERSHOOT_SPEED: f32 = 30.0; // in m/s
+// minimum seconds to stick with a behaviour so the AI doesn't dither between them
+const BEHAVIOUR_COMMITMENT: f32 = 0.75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Behaviour {
+    // point the nose straight at the target
+    Pursuit,
+    // point the nose where the target will be when the bullets arrive
+    LeadPursuit,
+    // turn hard into an attacker to spoil its shot
+    Break,
+    // fly straight at full power to regain speed
+    Extend,
+}
+
+/// Flies an aircraft by deciding its input each simulation step, the same way a player would
+#[derive(Debug, Clone)]
+pub struct AiPilot {
+    pub behaviour: Behaviour,
+    behaviour_timer: f32,
+    missile_cooldown: f32,
+}
+
+impl Default for AiPilot {
+    fn default() -> Self {
+        Self {
+            behaviour: Behaviour::Pursuit,
+            behaviour_timer: 0.0,
+            missile_cooldown: 0.0,
+        }
+    }
+}
+
+impl AiPilot {
+    /// decide what `me` should do this step
+    pub fn think(&mut self, me: &Player, world: &World, delta_time: &Duration) -> PlayerInput {
+        let dt = delta_time.as_secs_f32();
+        self.missile_cooldown -= dt;
+        self.behaviour_timer -= dt;
+
+        let mut input = PlayerInput {
+            roll: 0.0,
+            throttle_target: CRUISE_THROTTLE,
+            actions: Vec::new(),
+        };
+
+        // chase the closest enemy
+        let target = world.players.iter()
+            .filter(|p| p.team != me.team && p.is_alive())
+            .min_by(|a, b| me.pos.distance_squared(a.pos).total_cmp(&me.pos.distance_squared(b.pos)));
+        let Some(target) = target else {
+            return input;
+        };
+
+        let threat = find_threat(me, world);
+        self.choose_behaviour(me, target, threat);
+
+        match self.behaviour {
+            Behaviour::Break => {
+                // turn towards the attacker so it has to pull more lead than it can
+                let attacker = threat.unwrap_or(target.pos);
+                let error = math::angle_difference(me.rotation, math::heading_to(me.pos, attacker));
+                // break hard, never settle on a heading
+                input.roll = if error >= 0.0 { 1.0 } else { -1.0 };
+                input.throttle_target = me.aircraft.max_throttle();
+            }
+            Behaviour::Extend => {
+                // wings level and full power, turning without pulling the airspeed down any more is not an option
+                input.throttle_target = me.aircraft.max_throttle();
+            }
+            Behaviour::Pursuit | Behaviour::LeadPursuit => {
+                let aim_point = if self.behaviour == Behaviour::LeadPursuit {
+                    lead_point(me, target)
+                } else {
+                    target.pos
+                };
+                let error = math::angle_difference(me.rotation, math::heading_to(me.pos, aim_point));
+                input.roll = turn_towards(error);
+
+                // close the distance, but don't overshoot a slower target
+                let distance = me.pos.distance(target.pos);
+                if distance < OVERSHOOT_RANGE && me.speed > target.speed + OVERSHOOT_SPEED {
+                    input.actions.push(Action::Airbrake);
+                    input.throttle_target = 0.0;
+                } else if distance > THREAT_RANGE {
+                    input.throttle_target = 100.0;
+                }
+
+                // shoot when the aim point is in front and in range
+                let gun_range = me.aircraft.bullet_range * PIXELS_PER_METER;
+                if error.abs() < GUN_TOLERANCE && distance < gun_range && me.gun.ammo > 0 {
+                    input.actions.push(Action::FireGun);
+                }
+            }
+        }
+
+        if me.seeker.locked.is_some() && self.missile_cooldown <= 0.0 {
+            input.actions.push(Action::FireMissile);
+            self.missile_cooldown = MISSILE_INTERVAL;
+        }
+
+        input
+    }
+
+    fn choose_behaviour(&mut self, me: &Player, target: &Player, threat: Option<Vec2>) {
+        let corner_speed = me.aircraft.turn_flip_point;
+        let slow = me.speed < me.aircraft.stall_speed * EXTEND_SPEED;
+        let recovered = me.speed > corner_speed * RECOVERED_SPEED;
+
+        let next = if threat.is_some() {
+            // getting shot at beats everything else
+            Behaviour::Break
+        } else if self.behaviour_timer > 0.0 {
+            self.behaviour
+        } else if slow || (self.behaviour == Behaviour::Extend && !recovered) {
+            Behaviour::Extend
+        } else if me.pos.distance(target.pos) < me.aircraft.bullet_range * PIXELS_PER_METER {
+            Behaviour::LeadPursuit
+        } else {
+            Behaviour::Pursuit
+        };
+
+        if next != self.behaviour {
+            self.behaviour = next;
+            self.behaviour_timer = BEHAVIOUR_COMMITMENT;
+        }
+    }
+}
+
+/// where the missile or enemy aircraft most dangerous to `me` is, if one is after it and close
+fn find_threat(me: &Player, world: &World) -> Option<Vec2> {
+    // a missile guiding on us is the threat itself, whoever fired it
+    let missile = world.missiles.iter()
+        .filter(|m| m.target == Some(me.id) && m.pos.distance(me.pos) < THREAT_RANGE)
+        .min_by(|a, b| me.pos.distance_squared(a.pos).total_cmp(&me.pos.distance_squared(b.pos)));
+    if let Some(missile) = missile {
+        return Some(missile.pos);
+    }
+
+    world.players.iter()
+        .filter(|p| p.team != me.team && p.is_alive())
+        .filter(|p| p.pos.distance(me.pos) < THREAT_RANGE)
+        .find(|p| {
+            // about to fire a missile at us
+            if p.seeker.locked == Some(me.id) {
+                return true;
+            }
+            let off_their_nose = math::angle_difference(p.rotation, math::heading_to(p.pos, me.pos)).abs();
+            let off_our_nose = math::angle_difference(me.rotation, math::heading_to(me.pos, p.pos)).abs();
+            // pointed at us from somewhere we aren't pointed at them
+            off_their_nose < THREAT_ANGLE && off_our_nose > std::f32::consts::FRAC_PI_2
+        })
+        .map(|p| p.pos)
+}
+
+/// where to point so bullets fired now meet the target
+fn lead_point(me: &Player, target: &Player) -> Vec2 {
+    let bullet_speed = me.aircraft.bullet_speed * PIXELS_PER_METER;
+    let time_of_flight = me.pos.distance(target.pos) / bullet_speed;
+    target.pos + (target.velocity() - me.velocity()) * time_of_flight
+}
+
+/// how hard to roll towards a heading `error` radians away
+fn turn_towards(error: f32) -> f32 {
+    if error > HEADING_TOLERANCE {
+        1.0
+    } else if error < -HEADING_TOLERANCE {
+        -1.0
+    } else {
+        0.0
+    }
+}
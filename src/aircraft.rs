@@ -1,123 +1,31 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::path::Path;
 
-use macroquad::texture::{load_texture, Texture2D};
+use serde::{Deserialize, Serialize};
 
-pub enum AircraftType {
-    F16,
-    GripenE,
-    X47B,
-}
-
-impl AircraftType {
-
-    pub async fn get_aircraft(&self) -> Result<Aircraft, String> {
-        Ok(match self {
-            Self::F16 => {
-                let texture = load_texture("assets/sprites/aircraft/f16_level.png").await;
-                if let Err(e) = texture {
-                    return Err(e.to_string())
-                }
-                let texture = texture.unwrap();
-
-                let mut thrust_curve = ThrustCurve::new();
-                thrust_curve.add_point(0, 0);      // At 0% throttle, no thrust
-	            thrust_curve.add_point(25, 500);   // At 25% throttle, 500 units of thrust
-	            thrust_curve.add_point(50, 1500);  // At 50% throttle, 1500 units of thrust
-	            thrust_curve.add_point(75, 3000);  // At 75% throttle, 3000 units of thrust
-	            thrust_curve.add_point(85, 4000);  // At 85% throttle, 4000 units of thrust
-	            thrust_curve.add_point(90, 4800);  // At 90% throttle, 4800 units of thrust
-	            thrust_curve.add_point(100, 5000); // At 100% throttle, 5000 units of thrust
-	            thrust_curve.add_point(110, 6000); // At 110% throttle, 5500 units of thrust
-
-                Aircraft {
-                    name: "F-16".to_string(),
-                    sprite: texture,
-                    max_health: 100,
-
-                    base_turn_rate: 0.04,
-                    turn_flip_point: 100.0,
-                    max_turn_rate: 0.06,
-                    min_turn_rate: 0.025,
-                    
-                    stall_speed: 50.0,
-
-                    drag_base: 0.02,
-                    turn_drag: 1.2,
-
-                    airbrake_drag: 0.06,
-
-                    reference_area: 30.0,
-                    mass: 8000.0,
-                    thrust_multiplier: 4.9090909,
-
-                    bullet_fire_rate: 10, // ms
-
-                    thrust_curve,
-                }
-            }
-            Self::GripenE => {
-                let texture = load_texture("assets/sprites/aircraft/gripen_level.png").await;
-                    if let Err(e) = texture {
-                        return Err(e.to_string())
-                    }
-                    let texture = texture.unwrap();
-    
-                    let mut thrust_curve = ThrustCurve::new();
-                    thrust_curve.add_point(0, 0);      // At 0% throttle, no thrust
-                    thrust_curve.add_point(25, 500);   // At 25% throttle, 500 units of thrust
-                    thrust_curve.add_point(50, 1500);  // At 50% throttle, 1500 units of thrust
-                    thrust_curve.add_point(75, 3000);  // At 75% throttle, 3000 units of thrust
-                    thrust_curve.add_point(85, 4000);  // At 85% throttle, 4000 units of thrust
-                    thrust_curve.add_point(90, 4800);  // At 90% throttle, 4800 units of thrust
-                    thrust_curve.add_point(100, 5000); // At 100% throttle, 5000 units of thrust
-                    thrust_curve.add_point(110, 5500); // At 110% throttle, 5500 units of thrust
-    
-                    Aircraft {
-                        name: "Gripen".to_string(),
-                        sprite: texture,
-                        max_health: 100,
-    
-                        base_turn_rate: 0.04,
-                        turn_flip_point: 100.0,
-                        max_turn_rate: 0.06,
-                        min_turn_rate: 0.025,
-                        
-                        stall_speed: 50.0,
-    
-                        drag_base: 0.02,
-                        turn_drag: 1.2,
-    
-                        airbrake_drag: 0.06,
-    
-                        reference_area: 30.0,
-                        mass: 8000.0,
-                        thrust_multiplier: 4.9090909,
-    
-                        bullet_fire_rate: 10, // ms
-    
-                        thrust_curve,
-                    }
-            }
-            _ => {
-                return Err("Aircraft not implemented".to_string())
-            }
-        })
-    }
+use crate::{warn, weapons::missile::MissileStats};
 
-}
+/// directory that aircraft definitions are discovered in, one `<id>.json` file per airframe
+pub const AIRCRAFT_DIR: &str = "./data/aircraft";
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Aircraft {
+    // the file stem the aircraft was loaded from (i.e. `f16` for `f16.json`)
+    #[serde(skip)]
+    pub id: String,
     pub name: String,
-    pub sprite: Texture2D,
+    #[serde(default)]
+    pub description: String,
+    pub sprite: String, // path to the sprite texture, loaded by the asset manager
+    pub hitbox_radius: f32, // in pixels, also how far the nose is from the center
     pub max_health: u32, // should scale with size of plane and other aspects (i.e. a-10 has massive health but an f-16 has way smaller health)
 
-    pub base_turn_rate: f32,
-    pub turn_flip_point: f32,
-    pub max_turn_rate: f32,
-    pub min_turn_rate: f32,
+    pub base_turn_rate: f32, // in rad/s
+    pub turn_flip_point: f32, // in m/s
+    pub max_turn_rate: f32, // in rad/s
+    pub min_turn_rate: f32, // in rad/s
 
-    pub stall_speed: f32,
+    pub stall_speed: f32, // in m/s
 
     pub drag_base: f32,
     pub turn_drag: f32,
@@ -126,14 +34,101 @@ pub struct Aircraft {
     pub reference_area: f32, // in m^2
     pub mass: f32, // in kg
     pub thrust_multiplier: f32,
+    // whether the engine has an afterburner (allows throttle above 100%)
+    #[serde(default = "default_true")]
+    pub afterburner: bool,
 
     pub bullet_fire_rate: u32, // ms
+    pub bullet_damage: u32,
+    pub bullet_speed: f32, // muzzle velocity in m/s
+    pub bullet_range: f32, // in m
+    pub gun_ammo: u32,
+    // how many missiles fit in the aircraft's weapons bay
+    #[serde(default)]
+    pub missile_capacity: u32,
+    #[serde(default)]
+    pub missile: MissileStats,
 
     pub thrust_curve: ThrustCurve,
 }
 
+fn default_true() -> bool {
+    true
+}
+
 impl Aircraft {
 
+    /// load a single aircraft definition from a json file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut aircraft: Aircraft = serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+
+        aircraft.id = path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        aircraft.validate()
+            .map_err(|e| format!("Invalid aircraft {}: {}", path.display(), e))?;
+
+        Ok(aircraft)
+    }
+
+    // make sure the definition can actually be flown
+    fn validate(&self) -> Result<(), String> {
+        let top = match self.thrust_curve.points.keys().next_back() {
+            Some(&top) => top,
+            None => return Err("thrust curve has no points".to_string()),
+        };
+        if (top as f32) < self.max_throttle() {
+            return Err(format!("thrust curve ends at {}% but the throttle goes up to {}%", top, self.max_throttle()));
+        }
+        Ok(())
+    }
+
+    /// discover and load every aircraft definition in the given directory, sorted by id.
+    /// definitions that fail to load are skipped, it's only an error if none load
+    pub fn load_all<P: AsRef<Path>>(dir: P) -> Result<Vec<Self>, String> {
+        let dir = dir.as_ref();
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read aircraft directory {}: {}", dir.display(), e))?;
+
+        let mut aircraft = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                match Self::load(&path) {
+                    Ok(loaded) => aircraft.push(loaded),
+                    Err(e) => {
+                        warn!("{}, skipping it", e);
+                    }
+                }
+            }
+        }
+
+        if aircraft.is_empty() {
+            return Err(format!("No valid aircraft definitions found in {}", dir.display()));
+        }
+
+        aircraft.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(aircraft)
+    }
+
+    /// the highest throttle percentage the engine can be pushed to
+    pub fn max_throttle(&self) -> f32 {
+        if self.afterburner {
+            110.0
+        } else {
+            100.0
+        }
+    }
+
+    /// the fastest the aircraft can actually turn, turn rate falls off with speed so this is at the stall speed
+    pub fn peak_turn_rate(&self) -> f32 {
+        self.calc_turn_rate(self.stall_speed)
+    }
+
     pub fn calc_turn_rate(&self, speed: f32) -> f32 {
         // dark magic with linear regression (I dont understand this)
         let midpoint = self.turn_flip_point;
@@ -163,15 +158,16 @@ impl Aircraft {
 
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(transparent)]
 pub struct ThrustCurve {
-    pub points: HashMap<u32, u32>,
+    pub points: BTreeMap<u32, u32>,
 }
 
 impl ThrustCurve {
     pub fn new() -> Self {
         Self {
-            points: HashMap::new(),
+            points: BTreeMap::new(),
         }
     }
 
@@ -180,41 +176,48 @@ impl ThrustCurve {
     }
 
     pub fn get_thrust(&self, throttle_percent: u32, thrust_multiplier: f32) -> f32 {
-        // If there are no points, return 0 thrust
-        if self.points.is_empty() {
-            return 0.0;
-        }
-
-        // Collect the keys and sort them
-        let mut keys: Vec<&u32> = self.points.keys().collect();
-        keys.sort();
-
-        // Find the surrounding throttle points
-        let mut lower_key = keys[0];
-        let mut upper_key = keys[0];
-
-        for &key in &keys {
-            if key <= &throttle_percent {
-                lower_key = key;
+        // find the closest points at or below and at or above the throttle
+        let lower = self.points.range(..=throttle_percent).next_back();
+        let upper = self.points.range(throttle_percent..).next();
+
+        let thrust = match (lower, upper) {
+            // between two points, interpolate linearly
+            (Some((&lower_key, &lower_thrust)), Some((&upper_key, &upper_thrust))) if lower_key != upper_key => {
+                let fraction = (throttle_percent - lower_key) as f32 / (upper_key - lower_key) as f32;
+                lower_thrust as f32 + fraction * (upper_thrust as f32 - lower_thrust as f32)
             }
-            if key >= &throttle_percent {
-                upper_key = key;
-                break;
-            }
-        }
+            // exact match, or outside the curve so clamp to the nearest end
+            (Some((_, &thrust)), _) | (None, Some((_, &thrust))) => thrust as f32,
+            // no points at all
+            (None, None) => 0.0,
+        };
 
-        // If exact match, return the thrust at that throttle
-        if lower_key == upper_key {
-            return *self.points.get(lower_key).unwrap() as f32 * thrust_multiplier;
-        }
+        thrust * thrust_multiplier
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        // Perform linear interpolation
-        let lower_thrust = *self.points.get(lower_key).unwrap() as f32;
-        let upper_thrust = *self.points.get(upper_key).unwrap() as f32;
+    #[test]
+    fn thrust_clamps_past_the_last_point() {
+        let mut curve = ThrustCurve::new();
+        curve.add_point(0, 0);
+        curve.add_point(100, 5000);
 
-        let fraction = (throttle_percent - lower_key) as f32 / (upper_key - lower_key) as f32;
-        let interpolated_thrust = lower_thrust + fraction * (upper_thrust - lower_thrust);
+        assert_eq!(curve.get_thrust(50, 1.0), 2500.0);
+        assert_eq!(curve.get_thrust(110, 1.0), 5000.0);
+    }
 
-        interpolated_thrust * thrust_multiplier
+    #[test]
+    fn bundled_aircraft_are_valid() {
+        // load_all skips broken files, so load each one directly to see the error
+        for entry in std::fs::read_dir(AIRCRAFT_DIR).unwrap() {
+            let path = entry.unwrap().path();
+            if let Err(e) = Aircraft::load(&path) {
+                panic!("{}", e);
+            }
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use macroquad::{color::WHITE, math::Vec2, texture::{draw_texture_ex, load_texture, DrawTextureParams, Texture2D}};
+
+use crate::{aircraft::{Aircraft, AIRCRAFT_DIR}, audio::Sounds, boss::{BossDef, BOSS_DIR}, gamestate::GameStateError};
+
+pub const BULLET_SPRITE: &str = "assets/sprites/weapons/bullet.png";
+pub const MISSILE_SPRITE: &str = "assets/sprites/weapons/missile.png";
+
+/// draw a texture centered on `pos` and rotated around its center
+pub fn draw_centered(texture: &Texture2D, pos: Vec2, rotation: f32) {
+    draw_scaled(texture, pos, rotation, 1.0);
+}
+
+/// like `draw_centered` but scales the texture up or down by `scale`
+pub fn draw_scaled(texture: &Texture2D, pos: Vec2, rotation: f32, scale: f32) {
+    let size = texture.size() * scale;
+    draw_texture_ex(
+        texture,
+        pos.x - size.x / 2.0, pos.y - size.y / 2.0,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(size),
+            rotation,
+            ..Default::default()
+        }
+    );
+}
+
+/// Holds all data and textures loaded at startup so gamestates don't have to reload them from disk
+pub struct Assets {
+    aircraft: Vec<Aircraft>,
+    bosses: Vec<BossDef>,
+    textures: HashMap<String, Texture2D>,
+    sounds: Sounds,
+}
+
+impl Assets {
+    pub async fn load() -> Result<Self, String> {
+        let aircraft = Aircraft::load_all(AIRCRAFT_DIR)?;
+        let bosses = BossDef::load_all(BOSS_DIR)?;
+
+        // load each sprite once, even if multiple aircraft or bosses share it
+        let mut textures = HashMap::new();
+        let sprites = aircraft.iter().map(|a| (&a.id, &a.sprite))
+            .chain(bosses.iter().map(|b| (&b.id, &b.sprite)));
+        for (id, sprite) in sprites {
+            if textures.contains_key(sprite) {
+                continue;
+            }
+            let texture = load_texture(sprite).await
+                .map_err(|e| format!("Failed to load sprite for {}: {}", id, e))?;
+            textures.insert(sprite.clone(), texture);
+        }
+
+        for path in [BULLET_SPRITE, MISSILE_SPRITE] {
+            let texture = load_texture(path).await
+                .map_err(|e| format!("Failed to load sprite {}: {}", path, e))?;
+            textures.insert(path.to_string(), texture);
+        }
+
+        let sounds = Sounds::load().await?;
+
+        Ok(Self {
+            aircraft,
+            bosses,
+            textures,
+            sounds,
+        })
+    }
+
+    pub fn aircraft(&self) -> &[Aircraft] {
+        &self.aircraft
+    }
+
+    pub fn get_aircraft(&self, id: &str) -> Option<&Aircraft> {
+        self.aircraft.iter().find(|a| a.id == id)
+    }
+
+    pub fn bosses(&self) -> &[BossDef] {
+        &self.bosses
+    }
+
+    pub fn sounds(&self) -> &Sounds {
+        &self.sounds
+    }
+
+    pub fn texture(&self, path: &str) -> Option<&Texture2D> {
+        self.textures.get(path)
+    }
+
+    /// like `texture` but errors for gamestates drawing with it
+    pub fn require_texture(&self, path: &str) -> Result<&Texture2D, GameStateError> {
+        self.texture(path).ok_or_else(|| GameStateError::MissingAsset(path.to_string()))
+    }
+}
@@ -0,0 +1,111 @@
+use std::f32::consts::TAU;
+
+use macroquad::audio::{load_sound_from_bytes, play_sound, stop_sound, PlaySoundParams, Sound};
+
+use crate::player::Player;
+
+const SAMPLE_RATE: u32 = 44100;
+
+/// generate a 16 bit mono wav file of a sine tone, pulsed on and off `pulse_rate` times a second (0 for a continuous tone)
+pub fn tone_wav(frequency: f32, duration: f32, pulse_rate: f32) -> Vec<u8> {
+    let samples = (SAMPLE_RATE as f32 * duration) as u32;
+    let data_len = samples * 2;
+
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    // RIFF header
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    // format chunk: PCM, 1 channel, 16 bits per sample
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    // samples
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for i in 0..samples {
+        let t = i as f32 / SAMPLE_RATE as f32;
+        let on = pulse_rate <= 0.0 || (t * pulse_rate).fract() < 0.5;
+        let sample = if on { (t * frequency * TAU).sin() * 0.4 } else { 0.0 };
+        wav.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_le_bytes());
+    }
+
+    wav
+}
+
+/// Sound effects generated at startup, the game doesn't ship any audio files
+pub struct Sounds {
+    // growl while the seeker is tracking a target
+    pub seeker_tone: Sound,
+    // solid tone once the seeker has locked
+    pub lock_tone: Sound,
+}
+
+impl Sounds {
+    pub async fn load() -> Result<Self, String> {
+        let seeker_tone = load_sound_from_bytes(&tone_wav(400.0, 1.0, 6.0)).await
+            .map_err(|e| format!("Failed to generate seeker tone: {}", e))?;
+        let lock_tone = load_sound_from_bytes(&tone_wav(1200.0, 1.0, 0.0)).await
+            .map_err(|e| format!("Failed to generate lock tone: {}", e))?;
+
+        Ok(Self {
+            seeker_tone,
+            lock_tone,
+        })
+    }
+}
+
+/// What the missile seeker should sound like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockTone {
+    Silent,
+    Seeking,
+    Locked,
+}
+
+impl LockTone {
+    /// the tone the pilot of `player` should be hearing from its seeker
+    pub fn for_player(player: &Player) -> LockTone {
+        if player.launcher.missiles == 0 || !player.is_alive() {
+            LockTone::Silent
+        } else if player.seeker.locked.is_some() {
+            LockTone::Locked
+        } else if player.seeker.candidate.is_some() {
+            LockTone::Seeking
+        } else {
+            LockTone::Silent
+        }
+    }
+
+    /// stop the old tone and loop the new one at `volume` if the tone changed
+    pub fn transition(self, to: LockTone, sounds: &Sounds, volume: f32) -> LockTone {
+        if self == to {
+            return self;
+        }
+        self.stop(sounds);
+
+        let looped = PlaySoundParams {
+            looped: true,
+            volume,
+        };
+        match to {
+            LockTone::Seeking => play_sound(&sounds.seeker_tone, looped),
+            LockTone::Locked => play_sound(&sounds.lock_tone, looped),
+            LockTone::Silent => {}
+        }
+        to
+    }
+
+    pub fn stop(self, sounds: &Sounds) {
+        match self {
+            LockTone::Seeking => stop_sound(&sounds.seeker_tone),
+            LockTone::Locked => stop_sound(&sounds.lock_tone),
+            LockTone::Silent => {}
+        }
+    }
+}
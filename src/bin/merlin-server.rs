@@ -0,0 +1,142 @@
+use std::{net::SocketAddr, path::Path, process::ExitCode, sync::{atomic::{AtomicBool, Ordering}, Arc}};
+
+use merlin::{error, info, logging, warn, net::server::{GameMode, Server, ServerConfig, SERVER_CONFIG_PATH}};
+
+const USAGE: &str = "\
+Usage: merlin-server [options]
+
+Runs the game simulation without a window for clients to connect to.
+
+Options:
+    --config <path>       read settings from this file (default ./data/server.json if it exists)
+    --bind <addr>         address to listen on, i.e. 0.0.0.0:27015
+    --port <port>         port to listen on, keeping the configured address
+    --tick-rate <n>       simulation steps per second
+    --max-players <n>     how many clients can be connected at once
+    --mode <waves|pvp>    fight drone waves together or each other
+    --score-limit <n>     kills needed to win a pvp match, 0 for no limit
+    --time-limit <secs>   how long a pvp match lasts, 0 for no limit
+    --waves <path>        wave table to run
+    --ticks <n>           stop after this many steps, for automated tests
+    --log-level <filter>  debug, info, warn or error, i.e. info,merlin::net=debug (default from MERLIN_LOG)
+    --help                show this message";
+
+/// the server config and how long to run for
+struct Options {
+    config: ServerConfig,
+    ticks: Option<u64>,
+}
+
+fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        return Ok(None);
+    }
+
+    // the config file comes first so the rest of the arguments can override it
+    let config_path = args.iter().position(|arg| arg == "--config")
+        .map(|i| args.get(i + 1).cloned().ok_or("--config needs a path"))
+        .transpose()?;
+    let mut config = match config_path {
+        Some(path) => ServerConfig::load(path)?,
+        None if Path::new(SERVER_CONFIG_PATH).exists() => ServerConfig::load(SERVER_CONFIG_PATH)?,
+        None => ServerConfig::default(),
+    };
+    let mut ticks = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--config" => {
+                value()?;
+            }
+            "--bind" => {
+                config.bind = value()?.parse().map_err(|e| format!("Invalid address for --bind: {}", e))?;
+            }
+            "--port" => {
+                let port = value()?.parse().map_err(|e| format!("Invalid port for --port: {}", e))?;
+                config.bind = SocketAddr::new(config.bind.ip(), port);
+            }
+            "--tick-rate" => {
+                config.tick_rate = value()?.parse().map_err(|e| format!("Invalid number for --tick-rate: {}", e))?;
+            }
+            "--max-players" => {
+                config.max_players = value()?.parse().map_err(|e| format!("Invalid number for --max-players: {}", e))?;
+            }
+            "--mode" => {
+                config.mode = match value()?.to_lowercase().as_str() {
+                    "waves" => GameMode::Waves,
+                    "pvp" => GameMode::Pvp,
+                    mode => return Err(format!("Unknown mode '{}', expected waves or pvp", mode)),
+                };
+            }
+            "--score-limit" => {
+                config.pvp.score_limit = value()?.parse().map_err(|e| format!("Invalid number for --score-limit: {}", e))?;
+            }
+            "--time-limit" => {
+                config.pvp.time_limit = value()?.parse().map_err(|e| format!("Invalid number for --time-limit: {}", e))?;
+            }
+            "--waves" => {
+                config.waves = value()?.clone();
+            }
+            "--log-level" => {
+                logging::override_filter(value()?.parse()?);
+            }
+            "--ticks" => {
+                ticks = Some(value()?.parse().map_err(|e| format!("Invalid number for --ticks: {}", e))?);
+            }
+            _ => return Err(format!("Unknown argument '{}'", arg)),
+        }
+    }
+
+    config.validate()?;
+    Ok(Some(Options { config, ticks }))
+}
+
+fn main() -> ExitCode {
+    logging::install_panic_hook();
+    if let Ok(filter) = std::env::var("MERLIN_LOG") {
+        match filter.parse() {
+            Ok(filter) => logging::override_filter(filter),
+            Err(e) => {
+                error!("Invalid MERLIN_LOG: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            error!("{}", e);
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut server = match Server::from_config(&options.config) {
+        Ok(server) => server,
+        Err(e) => {
+            error!("Failed to start server: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let addr = server.local_addr().unwrap_or(options.config.bind);
+    info!("Listening on {} at {} ticks per second, playing {:?}", addr, options.config.tick_rate, options.config.mode);
+    // runs until ctrl+c unless a tick limit was given, stopping cleanly so clients are told the server is going away
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler_stop = stop.clone();
+    if let Err(e) = ctrlc::set_handler(move || handler_stop.store(true, Ordering::Relaxed)) {
+        warn!("Failed to listen for ctrl+c, clients won't be told when the server stops: {}", e);
+    }
+    server.run(&stop, options.ticks);
+    info!("Stopped after {} ticks with {} players connected", server.tick(), server.player_count());
+
+    ExitCode::SUCCESS
+}
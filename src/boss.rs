@@ -0,0 +1,352 @@
+use std::{f32::consts::{FRAC_PI_2, TAU}, path::Path};
+
+use macroquad::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::{collision::Hitbox, math, player::PIXELS_PER_METER, weapons::{gun::Bullet, missile::{Missile, MissileStats}}};
+
+/// directory that boss definitions are discovered in, one `<id>.json` file per boss
+pub const BOSS_DIR: &str = "./data/bosses";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ComponentKind {
+    // destroying the core destroys the boss
+    Core,
+    // fire bullets according to the current phase's pattern
+    Turret,
+    // each one lost slows the boss down
+    Engine,
+    // launch guided missiles at the closest enemy
+    MissilePod,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ComponentDef {
+    pub name: String,
+    pub kind: ComponentKind,
+    pub offset: [f32; 2], // from the boss' center when pointing up, in pixels
+    pub radius: f32, // in pixels
+    pub health: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AttackPattern {
+    // each turret fires single shots at the closest enemy
+    Aimed,
+    // each turret fires a fan of bullets towards the closest enemy
+    Spread,
+    // each turret fires in a rotating spiral regardless of where enemies are
+    Spiral,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Phase {
+    // the phase starts once the boss' health fraction drops to this
+    pub threshold: f32,
+    pub pattern: AttackPattern,
+    pub fire_interval: f32, // seconds between turret volleys
+    pub missile_interval: f32, // seconds between missile pod launches
+    pub speed_multiplier: f32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BossDef {
+    #[serde(skip)]
+    pub id: String,
+    pub name: String,
+    pub sprite: String,
+    pub scale: f32, // how much to scale the sprite up by
+
+    pub speed: f32, // in m/s
+    pub turn_rate: f32, // in rad/s
+    // how close the boss likes to get before circling its target, in pixels
+    pub standoff: f32,
+
+    pub bullet_damage: u32,
+    pub bullet_speed: f32, // in m/s
+    pub bullet_range: f32, // in m
+    #[serde(default)]
+    pub missile: MissileStats,
+
+    pub components: Vec<ComponentDef>,
+    pub phases: Vec<Phase>,
+}
+
+impl BossDef {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut boss: BossDef = serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+
+        if boss.phases.is_empty() {
+            return Err(format!("{} has no phases", path.display()));
+        }
+        // phases are checked from the highest threshold down
+        boss.phases.sort_by(|a, b| b.threshold.total_cmp(&a.threshold));
+
+        boss.id = path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Ok(boss)
+    }
+
+    /// discover and load every boss definition in the given directory, sorted by id
+    pub fn load_all<P: AsRef<Path>>(dir: P) -> Result<Vec<Self>, String> {
+        let dir = dir.as_ref();
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read boss directory {}: {}", dir.display(), e))?;
+
+        let mut bosses = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                bosses.push(Self::load(&path)?);
+            }
+        }
+
+        bosses.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(bosses)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Component {
+    pub def: ComponentDef,
+    pub health: u32,
+}
+
+impl Component {
+    pub fn is_alive(&self) -> bool {
+        self.health > 0
+    }
+}
+
+/// A large drone made of independently destroyable components
+#[derive(Debug, Clone)]
+pub struct Boss {
+    pub id: u32,
+    pub team: u32,
+    pub def: BossDef,
+
+    pub pos: Vec2,
+    pub prev_pos: Vec2,
+    pub rotation: f32,
+    pub prev_rotation: f32,
+
+    pub components: Vec<Component>,
+    pub phase: usize,
+
+    fire_timer: f32,
+    missile_timer: f32,
+    spiral_angle: f32,
+}
+
+impl Boss {
+    pub fn new(id: u32, team: u32, def: BossDef, pos: Vec2, rotation: f32) -> Self {
+        let components = def.components.iter()
+            .map(|c| Component { def: c.clone(), health: c.health })
+            .collect();
+
+        Self {
+            id,
+            team,
+            def,
+
+            pos,
+            prev_pos: pos,
+            rotation,
+            prev_rotation: rotation,
+
+            components,
+            phase: 0,
+
+            fire_timer: 0.0,
+            missile_timer: 0.0,
+            spiral_angle: 0.0,
+        }
+    }
+
+    pub fn health(&self) -> u32 {
+        self.components.iter().map(|c| c.health).sum()
+    }
+
+    pub fn max_health(&self) -> u32 {
+        self.components.iter().map(|c| c.def.health).sum()
+    }
+
+    pub fn health_fraction(&self) -> f32 {
+        self.health() as f32 / self.max_health().max(1) as f32
+    }
+
+    /// the boss dies with its core, or with its last component if it has no core
+    pub fn is_alive(&self) -> bool {
+        let mut cores = self.components.iter().filter(|c| c.def.kind == ComponentKind::Core).peekable();
+        if cores.peek().is_some() {
+            cores.all(|c| c.is_alive())
+        } else {
+            self.components.iter().any(|c| c.is_alive())
+        }
+    }
+
+    pub fn current_phase(&self) -> &Phase {
+        &self.def.phases[self.phase]
+    }
+
+    /// world position of a component, which rotates with the boss
+    pub fn component_pos(&self, component: &Component) -> Vec2 {
+        component_pos(component, self.pos, self.rotation)
+    }
+
+    /// like `component_pos` but for the interpolated position the boss is drawn at
+    pub fn interpolated_component_pos(&self, component: &Component, alpha: f32) -> Vec2 {
+        let (pos, rotation) = self.interpolated(alpha);
+        component_pos(component, pos, rotation)
+    }
+
+    /// distance from the boss' center to the outer edge of its furthest component
+    pub fn radius(&self) -> f32 {
+        self.components.iter()
+            .map(|c| Vec2::from(c.def.offset).length() + c.def.radius)
+            .fold(0.0, f32::max)
+    }
+
+    /// hitboxes of every component still standing, with their index
+    pub fn hitboxes(&self) -> impl Iterator<Item = (usize, Hitbox)> + '_ {
+        self.components.iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_alive())
+            .map(|(i, c)| (i, Hitbox::new(self.component_pos(c), c.def.radius)))
+    }
+
+    pub fn take_damage(&mut self, component: usize, damage: u32) {
+        if let Some(component) = self.components.get_mut(component) {
+            component.health = component.health.saturating_sub(damage);
+        }
+    }
+
+    pub fn interpolated(&self, alpha: f32) -> (Vec2, f32) {
+        (
+            self.prev_pos.lerp(self.pos, alpha),
+            self.prev_rotation + (self.rotation - self.prev_rotation) * alpha,
+        )
+    }
+
+    fn alive_of(&self, kind: ComponentKind) -> impl Iterator<Item = &Component> + '_ {
+        self.components.iter().filter(move |c| c.def.kind == kind && c.is_alive())
+    }
+
+    /// step the boss towards the closest of `targets` (id and position of every enemy),
+    /// returning the projectiles it fired
+    pub fn update(&mut self, targets: &[(u32, Vec2)], bounds: Vec2, delta_time: f32) -> (Vec<Bullet>, Vec<Missile>) {
+        self.prev_pos = self.pos;
+        self.prev_rotation = self.rotation;
+
+        // move on to the next phase once enough damage has been taken
+        let fraction = self.health_fraction();
+        while self.phase + 1 < self.def.phases.len() && fraction <= self.def.phases[self.phase + 1].threshold {
+            self.phase += 1;
+        }
+
+        let target = targets.iter()
+            .min_by(|a, b| self.pos.distance_squared(a.1).total_cmp(&self.pos.distance_squared(b.1)))
+            .copied();
+
+        self.steer(target.map(|t| t.1), bounds, delta_time);
+
+        let mut bullets = Vec::new();
+        let mut missiles = Vec::new();
+
+        self.fire_timer -= delta_time;
+        if self.fire_timer <= 0.0 {
+            self.fire_timer = self.current_phase().fire_interval;
+            bullets = self.fire_turrets(target.map(|t| t.1));
+        }
+
+        self.missile_timer -= delta_time;
+        if self.missile_timer <= 0.0 {
+            self.missile_timer = self.current_phase().missile_interval;
+            if let Some((target_id, _)) = target {
+                for pod in self.alive_of(ComponentKind::MissilePod) {
+                    let pos = self.component_pos(pod);
+                    missiles.push(Missile::launch(self.id, self.team, pos, self.rotation, self.def.speed, Some(target_id), self.def.missile.clone()));
+                }
+            }
+        }
+
+        (bullets, missiles)
+    }
+
+    fn steer(&mut self, target: Option<Vec2>, bounds: Vec2, delta_time: f32) {
+        // engines lost slow the boss down, but it never stops completely
+        let engines = self.components.iter().filter(|c| c.def.kind == ComponentKind::Engine).count();
+        let engine_factor = if engines == 0 {
+            1.0
+        } else {
+            (self.alive_of(ComponentKind::Engine).count() as f32 / engines as f32).max(0.25)
+        };
+        let speed = self.def.speed * self.current_phase().speed_multiplier * engine_factor;
+
+        let inside = self.pos.x >= 0.0 && self.pos.x <= bounds.x && self.pos.y >= 0.0 && self.pos.y <= bounds.y;
+        let desired = match target {
+            // come back into the fight if it wandered off the screen
+            _ if !inside => Some(math::heading_to(self.pos, bounds / 2.0)),
+            // circle the target once close enough
+            Some(target) if self.pos.distance(target) < self.def.standoff => Some(math::heading_to(self.pos, target) + FRAC_PI_2),
+            Some(target) => Some(math::heading_to(self.pos, target)),
+            None => None,
+        };
+        if let Some(desired) = desired {
+            let turn = math::angle_difference(self.rotation, desired);
+            let max_turn = self.def.turn_rate * delta_time;
+            self.rotation += turn.clamp(-max_turn, max_turn);
+        }
+
+        self.pos += math::direction(self.rotation) * speed * PIXELS_PER_METER * delta_time;
+    }
+
+    fn fire_turrets(&mut self, target: Option<Vec2>) -> Vec<Bullet> {
+        let pattern = self.current_phase().pattern;
+        let speed = self.def.bullet_speed * PIXELS_PER_METER;
+        let lifetime = self.def.bullet_range / self.def.bullet_speed;
+
+        let mut headings = Vec::new();
+        let mut bullets = Vec::new();
+        for turret in self.alive_of(ComponentKind::Turret) {
+            let pos = self.component_pos(turret);
+            headings.clear();
+            match (pattern, target) {
+                (AttackPattern::Aimed, Some(target)) => headings.push(math::heading_to(pos, target)),
+                (AttackPattern::Spread, Some(target)) => {
+                    let center = math::heading_to(pos, target);
+                    headings.extend([-0.3, -0.15, 0.0, 0.15, 0.3].iter().map(|offset| center + offset));
+                }
+                (AttackPattern::Spiral, _) => {
+                    headings.extend((0..4).map(|i| self.spiral_angle + i as f32 * TAU / 4.0));
+                }
+                // nothing to aim at
+                _ => {}
+            }
+
+            for heading in &headings {
+                bullets.push(Bullet {
+                    owner: self.id,
+                    team: self.team,
+                    pos,
+                    prev_pos: pos,
+                    velocity: math::direction(*heading) * speed,
+                    lifetime,
+                    damage: self.def.bullet_damage,
+                });
+            }
+        }
+        self.spiral_angle += 0.3;
+
+        bullets
+    }
+}
+
+fn component_pos(component: &Component, pos: Vec2, rotation: f32) -> Vec2 {
+    pos + Vec2::from_angle(rotation).rotate(Vec2::from(component.def.offset))
+}
@@ -0,0 +1,40 @@
+use macroquad::math::Vec2;
+
+/// A circular collision shape in world space (pixels)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hitbox {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+impl Hitbox {
+    pub fn new(center: Vec2, radius: f32) -> Self {
+        Self {
+            center,
+            radius,
+        }
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.center.distance_squared(point) <= self.radius * self.radius
+    }
+
+    pub fn intersects(&self, other: &Hitbox) -> bool {
+        let radii = self.radius + other.radius;
+        self.center.distance_squared(other.center) <= radii * radii
+    }
+
+    /// whether the path from `start` to `end` passes through the hitbox.
+    /// projectiles can move further than a hitbox is wide in a single step, so checking only the end point would miss
+    pub fn intersects_segment(&self, start: Vec2, end: Vec2) -> bool {
+        let segment = end - start;
+        let length_squared = segment.length_squared();
+        if length_squared == 0.0 {
+            return self.contains(start);
+        }
+
+        // closest point on the segment to the center
+        let t = ((self.center - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+        self.contains(start + segment * t)
+    }
+}
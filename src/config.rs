@@ -0,0 +1,68 @@
+use std::{fmt::{self, Display, Formatter}, io, path::{Path, PathBuf}};
+
+/// Something that went wrong reading or writing one of the game's config files
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, String),
+    Serialize(PathBuf, String),
+    Write(PathBuf, io::Error),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "Failed to read {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "Failed to parse {}: {}", path.display(), e),
+            ConfigError::Serialize(path, e) => write!(f, "Failed to serialize {}: {}", path.display(), e),
+            ConfigError::Write(path, e) => write!(f, "Failed to write {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// the contents of the file at `path`, or None if there isn't one yet
+pub fn read(path: &Path) -> Result<Option<String>, ConfigError> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(ConfigError::Read(path.to_path_buf(), e)),
+    }
+}
+
+/// replace the file at `path` with `contents`, creating its directory if needed.
+/// the contents go to a temporary file first and are renamed over the old one,
+/// so a crash part way through leaves either the old file or the new one, never half of each
+pub fn write_atomic(path: &Path, contents: &str) -> Result<(), ConfigError> {
+    let error = |e| ConfigError::Write(path.to_path_buf(), e);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(error)?;
+    }
+
+    let temp = with_suffix(path, "tmp");
+    if let Err(e) = std::fs::write(&temp, contents) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(error(e));
+    }
+    std::fs::rename(&temp, path).map_err(|e| {
+        let _ = std::fs::remove_file(&temp);
+        error(e)
+    })
+}
+
+/// move a file that couldn't be understood out of the way so it isn't lost when the defaults are written over it
+pub fn back_up_corrupt(path: &Path) -> Result<PathBuf, ConfigError> {
+    let backup = with_suffix(path, "corrupt");
+    std::fs::rename(path, &backup).map_err(|e| ConfigError::Write(backup.clone(), e))?;
+    Ok(backup)
+}
+
+// controls.dat -> controls.dat.<suffix>
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
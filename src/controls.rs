@@ -1,8 +1,11 @@
 use std::fmt::Display;
-use std::{collections::HashMap, fmt::Formatter};
+use std::{collections::{hash_map::Entry, HashMap, HashSet}, fmt::Formatter};
 use std::path::Path;
-use macroquad::input::{is_key_down, is_key_released, is_mouse_button_down, is_mouse_button_released, KeyCode, MouseButton};
+use macroquad::{input::{is_key_down, is_mouse_button_down, KeyCode, MouseButton}, math::Vec2};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{config::{self, ConfigError}, gamepad::{self, Axis, Button}, info, math, player::{Player, PlayerInput}, settings::SteeringMode, storage, warn};
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
 pub enum Action {
@@ -10,11 +13,30 @@ pub enum Action {
     RollRight,
     ThrottleUp,
     ThrottleDown,
+    // sets the throttle to how far its binding is pushed, for analog triggers and throttle levers
+    Throttle,
     Airbrake,
     FireGun,
     FireMissile,
     
     Pause,
+    Scoreboard,
+}
+
+impl Action {
+    /// every action, in the order they are listed in the controls screen
+    pub const ALL: [Action; 10] = [
+        Action::RollLeft,
+        Action::RollRight,
+        Action::ThrottleUp,
+        Action::ThrottleDown,
+        Action::Throttle,
+        Action::Airbrake,
+        Action::FireGun,
+        Action::FireMissile,
+        Action::Pause,
+        Action::Scoreboard,
+    ];
 }
 
 impl Display for Action {
@@ -24,10 +46,12 @@ impl Display for Action {
             Action::RollRight => write!(f, "Roll Right"),
             Action::ThrottleUp => write!(f, "Throttle Up"),
             Action::ThrottleDown => write!(f, "Throttle Down"),
+            Action::Throttle => write!(f, "Throttle"),
             Action::Airbrake => write!(f, "Airbrake"),
             Action::FireGun => write!(f, "Fire Gun"),
             Action::FireMissile => write!(f, "Fire Missile"),
             Action::Pause => write!(f, "Pause"),
+            Action::Scoreboard => write!(f, "Scoreboard"),
         }
     }
 }
@@ -36,172 +60,600 @@ impl Display for Action {
 pub enum Binding {
     Key(u16),
     Mouse(u16),
+    // analog on gamepads with pressure sensitive triggers
+    Gamepad(Button),
+    // one half of a gamepad axis, pushed towards the positive end if `positive`
+    Axis(Axis, bool),
 }
 
-impl Into<u16> for Binding {
-    fn into(self) -> u16 {
+impl Binding {
+    pub fn is_gamepad(&self) -> bool {
+        matches!(self, Binding::Gamepad(_) | Binding::Axis(..))
+    }
+
+    /// how far the input is pushed from 0.0 to 1.0, before any dead zone or response curve
+    fn raw_value(&self) -> f32 {
+        let held = |down: bool| if down { 1.0 } else { 0.0 };
         match self {
+            Binding::Key(key) => held(is_key_down(u16_to_keycode(*key))),
+            Binding::Mouse(mb) => held(is_mouse_button_down(u16_to_mousecode(*mb))),
+            Binding::Gamepad(button) => gamepad::button_value(*button),
+            Binding::Axis(axis, positive) => {
+                let value = gamepad::axis(*axis);
+                if *positive { value.max(0.0) } else { (-value).max(0.0) }
+            }
+        }
+    }
+}
+
+impl From<Binding> for u16 {
+    fn from(binding: Binding) -> u16 {
+        match binding {
             Binding::Key(k) => k,
             Binding::Mouse(m) => m,
+            Binding::Gamepad(button) => button as u16,
+            Binding::Axis(axis, _) => axis as u16,
+        }
+    }
+}
+
+impl Display for Binding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Binding::Key(k) => write!(f, "{:?}", u16_to_keycode(*k)),
+            Binding::Mouse(m) => write!(f, "Mouse {:?}", u16_to_mousecode(*m)),
+            Binding::Gamepad(button) => write!(f, "Pad {:?}", button),
+            Binding::Axis(axis, positive) => write!(f, "{:?}{}", axis, if *positive { "+" } else { "-" }),
+        }
+    }
+}
+
+/// a keybind as the player would read it, i.e. "LeftControl + W"
+pub fn describe_bindings(bindings: &[Binding]) -> String {
+    if bindings.is_empty() {
+        return "Unbound".to_string();
+    }
+    bindings.iter().map(|b| b.to_string()).collect::<Vec<_>>().join(" + ")
+}
+
+/// How holding down an action's keybind turns into the action being active
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub enum ActivationMode {
+    // active for as long as the keybind is held
+    #[default]
+    Hold,
+    // each press switches the action on or off
+    Toggle,
+    // active once each time the keybind goes down, until it has been acted on
+    Press,
+}
+
+impl ActivationMode {
+    /// the mode after this one, for cycling through them in the controls screen
+    pub fn next(self) -> Self {
+        match self {
+            ActivationMode::Hold => ActivationMode::Toggle,
+            ActivationMode::Toggle => ActivationMode::Press,
+            ActivationMode::Press => ActivationMode::Hold,
+        }
+    }
+}
+
+impl Display for ActivationMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActivationMode::Hold => write!(f, "Hold"),
+            ActivationMode::Toggle => write!(f, "Toggle"),
+            ActivationMode::Press => write!(f, "Press"),
+        }
+    }
+}
+
+/// How an analog input is shaped before it drives an action
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AxisResponse {
+    // how far the input has to move before it counts, from 0.0 to 1.0
+    pub dead_zone: f32,
+    // 1.0 is linear, higher gives finer control near the center at the cost of the edges
+    pub exponent: f32,
+}
+
+impl AxisResponse {
+    /// shape a raw input from 0.0 to 1.0, anything inside the dead zone is 0.0
+    pub fn apply(&self, raw: f32) -> f32 {
+        if raw <= self.dead_zone {
+            return 0.0;
         }
+        let scaled = (raw - self.dead_zone) / (1.0 - self.dead_zone).max(f32::EPSILON);
+        scaled.clamp(0.0, 1.0).powf(self.exponent)
     }
 }
 
-// TODO: ControLHandler controls that are toggle should use get_actions_released() while held controls should use get_actions_down(). 
-//   The ControlHandler does not store anything outside of key action pairs in the file so toggle/held keybind settings must be done separate
+impl Default for AxisResponse {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.15,
+            exponent: 1.0,
+        }
+    }
+}
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// The inputs that have to be held together to trigger an action, and how they trigger it
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Keybind {
+    // keyboard and mouse
+    pub keys: Vec<Binding>,
+    // a separate binding so the same controls work on a keyboard or a gamepad
+    #[serde(default)]
+    pub gamepad: Vec<Binding>,
+    #[serde(default)]
+    pub mode: ActivationMode,
+    #[serde(default)]
+    pub response: AxisResponse,
+}
+
+impl Keybind {
+    pub fn new(keys: Vec<Binding>, gamepad: Vec<Binding>, mode: ActivationMode) -> Self {
+        Self {
+            keys,
+            gamepad,
+            mode,
+            response: AxisResponse::default(),
+        }
+    }
+
+    pub fn with_response(mut self, response: AxisResponse) -> Self {
+        self.response = response;
+        self
+    }
+
+    /// how strongly the keybind is held from 0.0 to 1.0, the weakest analog input in a combination decides
+    pub fn value(&self) -> f32 {
+        let chord = |bindings: &[Binding]| {
+            if bindings.is_empty() {
+                return 0.0;
+            }
+            bindings.iter()
+                .map(|b| self.response.apply(b.raw_value()))
+                .fold(1.0, f32::min)
+        };
+        chord(&self.keys).max(chord(&self.gamepad))
+    }
+}
+
+const CONTROLS_FILE: &str = "controls.dat";
+
+// how fast the throttle target moves while the throttle keys are held, in percent per second
+pub const THROTTLE_RATE: f32 = 60.0;
+
+// the layout controls.dat is written in, bump it when the layout changes and teach `migrate` to bring older files up to date
+pub const CONTROLS_VERSION: u32 = 3;
+
+/// controls.dat as it is stored on disk, read loosely so old and damaged files can still be made sense of
+#[derive(Deserialize, Serialize)]
+struct ControlsFile {
+    // files from before the version was stored are version 1
+    #[serde(default = "unversioned")]
+    version: u32,
+    bindings: serde_json::Map<String, Value>,
+}
+
+fn unversioned() -> u32 {
+    1
+}
+
+/// bring `file` up to CONTROLS_VERSION one version at a time
+fn migrate(file: &mut ControlsFile) {
+    if file.version < 2 {
+        // version 1 only stored the keys of each action, everything added since comes from the defaults
+        let defaults = ControlHandler::default();
+        for (name, value) in file.bindings.iter_mut() {
+            let Some(default) = parse_action(name).and_then(|action| defaults.bindings.get(&action)) else {
+                // unknown actions are dropped once the file is loaded
+                continue;
+            };
+            let Ok(Value::Object(mut keybind)) = serde_json::to_value(default) else {
+                continue;
+            };
+            match value.take() {
+                Value::Object(fields) => keybind.extend(fields),
+                keys => {
+                    keybind.insert("keys".to_string(), keys);
+                }
+            }
+            *value = Value::Object(keybind);
+        }
+        file.version = 2;
+    }
+    if file.version < 3 {
+        // version 2 also stored the steering mode, which is read from here into the settings the first time they are created
+        file.version = 3;
+    }
+}
+
+/// the steering mode controls.dat held before it moved into the settings, if it has one
+pub fn legacy_steering() -> Option<SteeringMode> {
+    let contents = config::read(&storage::config_file(CONTROLS_FILE)).ok()??;
+    let file: Value = serde_json::from_str(&contents).ok()?;
+    serde_json::from_value(file.get("steering")?.clone()).ok()
+}
+
+fn parse_action(name: &str) -> Option<Action> {
+    serde_json::from_value(Value::String(name.to_string())).ok()
+}
+
+/// What the keybinds have done so far, kept between frames to resolve toggles and presses
+#[derive(Debug, Clone, Default)]
+struct ActionState {
+    // false until the first update, so anything already held when the controls were loaded isn't a fresh press
+    primed: bool,
+    // how strongly each action's keybind was held at the last update, anything above 0.0 is held
+    values: HashMap<Action, f32>,
+    toggled: HashSet<Action>,
+    // press actions waiting to be acted on
+    pressed: HashSet<Action>,
+    // actions that became active at the last update
+    activated: HashSet<Action>,
+    // whether the throttle follows the Throttle keybind, from when it's moved until the throttle keys are used
+    throttle_lever: bool,
+}
+
+#[derive(Debug, Clone)]
 pub struct ControlHandler {
-    bindings: HashMap<Action, Vec<Binding>>,
+    bindings: HashMap<Action, Keybind>,
+    state: ActionState,
 }
 
 impl ControlHandler {
-    fn create_default_control_mapping() -> Result<(), String> {
-        let raw_path = "./data/controls.dat".to_string();
-        let path = Path::new(&raw_path);
-        
-        if !path.exists() {
-            // make the directories
-            if let Err(e) = std::fs::create_dir_all(path.parent().unwrap()) {
-                return Err(format!("Failed to create data directory: {}", e));
+    /// read the contents of controls.dat, returning whether anything had to change to load it so it can be saved back
+    fn parse(contents: &str) -> Result<(Self, bool), serde_json::Error> {
+        let mut file: ControlsFile = serde_json::from_str(contents)?;
+        let mut changed = false;
+
+        if file.version > CONTROLS_VERSION {
+            warn!("controls.dat is from a newer version ({}), loading what can be understood", file.version);
+        } else if file.version < CONTROLS_VERSION {
+            info!("Migrating controls.dat from version {} to {}", file.version, CONTROLS_VERSION);
+            migrate(&mut file);
+            changed = true;
+        }
+
+        let mut bindings = HashMap::new();
+        for (name, value) in file.bindings {
+            let Some(action) = parse_action(&name) else {
+                warn!("Dropping unknown action '{}' from controls.dat", name);
+                changed = true;
+                continue;
+            };
+            match serde_json::from_value(value) {
+                Ok(keybind) => {
+                    bindings.insert(action, keybind);
+                }
+                Err(e) => {
+                    warn!("Invalid keybind for {} in controls.dat, using the default: {}", action, e);
+                    changed = true;
+                }
             }
-            // create the file
-            if let Err(e) = std::fs::File::create(path) {
-                return Err(format!("Failed to create controls.dat: {}", e));
+        }
+
+        // actions added since the file was written start with their default keybind
+        for (action, keybind) in Self::default().bindings {
+            if let Entry::Vacant(entry) = bindings.entry(action) {
+                info!("Adding the default keybind for {} to controls.dat", entry.key());
+                entry.insert(keybind);
+                changed = true;
             }
         }
-        
-        // serialize default values
-        let deflt = ControlHandler::default();
-        let serialized = serde_json::to_string(&deflt).unwrap();
-        
-        // write the defaults to the file
-        std::fs::write(path, serialized).unwrap();
-        
-        Ok(())
+
+        let handler = Self {
+            bindings,
+            state: ActionState::default(),
+        };
+        Ok((handler, changed))
     }
-    
-    pub fn load() -> Self {
-        let raw_path = "./data/controls.dat".to_string();
-        let path = Path::new(&raw_path);
-        
-        if !path.exists() {
-            Self::create_default_control_mapping().unwrap();
-        }
-        
-        let contents = std::fs::read_to_string(path).unwrap();
-        
-        serde_json::from_str(&contents).unwrap()
+
+    /// the contents of controls.dat for these controls
+    fn serialize(&self) -> Result<String, ConfigError> {
+        let bindings = self.bindings.iter()
+            .filter_map(|(action, keybind)| {
+                let name = serde_json::to_value(action).ok()?.as_str()?.to_string();
+                Some((name, serde_json::to_value(keybind).ok()?))
+            })
+            .collect();
+        let file = ControlsFile {
+            version: CONTROLS_VERSION,
+            bindings,
+        };
+        serde_json::to_string(&file)
+            .map_err(|e| ConfigError::Serialize(storage::config_file(CONTROLS_FILE), e.to_string()))
     }
-    
-    pub fn save(&self) {
-        let raw_path = "./data/controls.dat".to_string();
-        let path = Path::new(&raw_path);
-        
-        if !path.exists() {
-            Self::create_default_control_mapping().unwrap();
+
+    /// load the keybinds from controls.dat, falling back to the defaults if it is missing or can't be used
+    pub fn load() -> Self {
+        let mut path = storage::config_file(CONTROLS_FILE);
+        // controls from before they moved to the config directory are carried over the first time
+        let legacy = Path::new(storage::LEGACY_DIR).join(CONTROLS_FILE);
+        let mut imported = false;
+        if !path.exists() && legacy.exists() {
+            info!("Importing controls from {}", legacy.display());
+            path = legacy;
+            imported = true;
         }
-        
-        let serialized = serde_json::to_string(self).unwrap();
-        
-        std::fs::write(path, serialized).unwrap();
-    }
-    
-    pub fn get_actions_down(&self) -> Vec<Action> {
-        let mut pressed = Vec::new();
-        
-        for (action, keys) in &self.bindings {
-            let mut is_pressed = true;
-            for bind in keys {
-                match bind {
-                    Binding::Key(key) => {
-                        // if any of the keys are not pressed in a keybind (macro), then set pressed to false
-                        if !is_key_down(u16_to_keycode(*key)) {
-                            is_pressed = false;
-                        }
+
+        let contents = match config::read(&path) {
+            Ok(Some(contents)) => contents,
+            Ok(None) => {
+                info!("No controls.dat found, creating one with the default controls");
+                return Self::default().save_or_warn();
+            }
+            Err(e) => {
+                // the file may be fine and just unreadable right now, so leave it alone
+                warn!("{}, using the default controls", e);
+                return Self::default();
+            }
+        };
+
+        match Self::parse(&contents) {
+            // write back anything that was imported, migrated, merged or dropped so it only happens once
+            Ok((handler, changed)) if changed || imported => handler.save_or_warn(),
+            Ok((handler, _)) => handler,
+            Err(e) => {
+                warn!("{}, using the default controls", ConfigError::Parse(path.clone(), e.to_string()));
+                match config::back_up_corrupt(&path) {
+                    Ok(backup) => {
+                        info!("Moved the unreadable controls to {}", backup.display());
                     }
-                    Binding::Mouse(mb) => {
-                        // if any of the keys are not pressed in a keybind (macro), then set pressed to false
-                        if !is_mouse_button_down(u16_to_mousecode(*mb)) {
-                            is_pressed = false;
-                        }
+                    Err(e) => {
+                        // keep the old file rather than write over bindings that might be recovered by hand
+                        warn!("{}", e);
+                        return Self::default();
                     }
                 }
-            }
-            
-            if is_pressed {
-                pressed.push(action.clone());
+                Self::default().save_or_warn()
             }
         }
-        
-        // todo: mouse buttons too!
+    }
 
-        pressed
+    pub fn save(&self) -> Result<(), ConfigError> {
+        config::write_atomic(&storage::config_file(CONTROLS_FILE), &self.serialize()?)
+    }
+
+    // for saves nobody is waiting on, where the controls still work for this session either way
+    fn save_or_warn(self) -> Self {
+        if let Err(e) = self.save() {
+            warn!("{}", e);
+        }
+        self
+    }
+    
+    /// load the keybinds from disk again, keeping anything currently toggled on
+    pub fn reload(&mut self) {
+        let toggled = std::mem::take(&mut self.state.toggled);
+        *self = Self::load();
+        self.state.toggled = toggled;
     }
 
-    pub fn get_actions_up(&self) -> Vec<Action> {
-        let mut released = Vec::new();
+    /// read the keybinds and resolve every action's mode, called once per frame before the actions are used
+    pub fn update(&mut self) {
+        let values: HashMap<Action, f32> = self.bindings.iter()
+            .map(|(action, keybind)| (action.clone(), keybind.value()))
+            .collect();
+        let was_active: HashSet<Action> = self.get_active_actions().into_iter().collect();
+        let primed = self.state.primed;
 
-        for (action, keys) in &self.bindings {
-            let mut is_released = true;
-            for bind in keys {
-                match bind {
-                    Binding::Key(key) => {
-                        // if any of the keys are not pressed in a keybind (macro), then set pressed to false
-                        if !is_key_released(u16_to_keycode(*key)) {
-                            is_released = false;
+        if primed {
+            for (action, value) in &values {
+                let was_held = self.state.values.get(action).is_some_and(|v| *v > 0.0);
+                if *value <= 0.0 || was_held {
+                    continue;
+                }
+                match self.get_mode(action) {
+                    ActivationMode::Hold => {}
+                    ActivationMode::Toggle => {
+                        if !self.state.toggled.remove(action) {
+                            self.state.toggled.insert(action.clone());
                         }
                     }
-                    Binding::Mouse(mb) => {
-                        // if any of the keys are not pressed in a keybind (macro), then set pressed to false
-                        if !is_mouse_button_released(u16_to_mousecode(*mb)) {
-                            is_released = false;
-                        }
+                    ActivationMode::Press => {
+                        self.state.pressed.insert(action.clone());
                     }
                 }
             }
-            
-            if is_released {
-                released.push(action.clone());
-            }
         }
 
-        released
+        // whichever throttle control was touched last is in charge of the throttle
+        let value = |values: &HashMap<Action, f32>, action: &Action| values.get(action).copied().unwrap_or(0.0);
+        if primed && value(&values, &Action::Throttle) != value(&self.state.values, &Action::Throttle) {
+            self.state.throttle_lever = true;
+        }
+        if value(&values, &Action::ThrottleUp) > 0.0 || value(&values, &Action::ThrottleDown) > 0.0 {
+            self.state.throttle_lever = false;
+        }
+
+        self.state.primed = true;
+        self.state.values = values;
+        self.state.activated = if primed {
+            self.get_active_actions().into_iter()
+                .filter(|action| !was_active.contains(action))
+                .collect()
+        } else {
+            HashSet::new()
+        };
     }
 
-    pub fn is_action_pressed(&self, action: Action) -> bool {
-        self.get_actions_down().contains(&action)
+    pub fn is_action_active(&self, action: &Action) -> bool {
+        match self.get_mode(action) {
+            ActivationMode::Hold => self.action_value(action) > 0.0,
+            ActivationMode::Toggle => self.state.toggled.contains(action),
+            ActivationMode::Press => self.state.pressed.contains(action),
+        }
+    }
+
+    /// how strongly `action` is applied from 0.0 to 1.0, analog for held actions and all or nothing for the rest
+    pub fn action_value(&self, action: &Action) -> f32 {
+        match self.get_mode(action) {
+            ActivationMode::Hold => self.state.values.get(action).copied().unwrap_or(0.0),
+            _ => if self.is_action_active(action) { 1.0 } else { 0.0 },
+        }
+    }
+
+    /// true if `action` became active at the last update, for things that should only happen once however the action is bound
+    pub fn was_action_activated(&self, action: &Action) -> bool {
+        self.state.activated.contains(action)
+    }
+
+    /// every action that is active as of the last update
+    pub fn get_active_actions(&self) -> Vec<Action> {
+        Action::ALL.iter()
+            .filter(|action| self.is_action_active(action))
+            .cloned()
+            .collect()
+    }
+
+    /// what the player is asking `player` to do, the throttle target carries on from `previous`.
+    /// `cursor` is where the mouse is in the world when steering with mouse aim, the roll actions still override it
+    pub fn player_input(&self, previous: &PlayerInput, player: &Player, cursor: Option<Vec2>, delta_time: f32) -> PlayerInput {
+        let mut roll = self.action_value(&Action::RollRight) - self.action_value(&Action::RollLeft);
+        if let Some(cursor) = cursor.filter(|_| roll == 0.0) {
+            roll = mouse_aim_roll(player, cursor, delta_time);
+        }
+        let max_throttle = player.aircraft.max_throttle();
+        let throttle_target = if self.state.throttle_lever {
+            self.action_value(&Action::Throttle) * max_throttle
+        } else {
+            let command = self.action_value(&Action::ThrottleUp) - self.action_value(&Action::ThrottleDown);
+            move_throttle(previous.throttle_target, command, max_throttle, delta_time)
+        };
+
+        PlayerInput {
+            roll,
+            throttle_target,
+            actions: self.get_active_actions(),
+        }
+    }
+
+    /// press actions stay active until this is called, so they aren't lost on frames without a simulation step
+    pub fn consume_presses(&mut self) {
+        self.state.pressed.clear();
     }
     
-    pub fn edit_keybind(&mut self, action: Action, new_key: Vec<Binding>) {
-        self.bindings.insert(action, new_key.iter().map(|k| *k).collect());
+    /// replace the keyboard and mouse keybind of `action`, or its gamepad one if `new_key` uses the gamepad.
+    /// the change is kept for this session even if it can't be saved
+    pub fn edit_keybind(&mut self, action: Action, new_key: Vec<Binding>) -> Result<(), ConfigError> {
+        let keybind = self.bindings.entry(action)
+            .or_insert_with(|| Keybind::new(Vec::new(), Vec::new(), ActivationMode::default()));
+        if new_key.iter().any(Binding::is_gamepad) {
+            keybind.gamepad = new_key;
+        } else {
+            keybind.keys = new_key;
+        }
         
-        self.save();
+        self.save()
+    }
+
+    pub fn set_mode(&mut self, action: Action, mode: ActivationMode) -> Result<(), ConfigError> {
+        self.bindings.entry(action)
+            .or_insert_with(|| Keybind::new(Vec::new(), Vec::new(), mode))
+            .mode = mode;
+
+        self.save()
+    }
+
+    pub fn get_bindings(&self, action: &Action) -> &[Binding] {
+        self.bindings.get(action).map(|b| b.keys.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn get_gamepad_bindings(&self, action: &Action) -> &[Binding] {
+        self.bindings.get(action).map(|b| b.gamepad.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn get_mode(&self, action: &Action) -> ActivationMode {
+        self.bindings.get(action).map(|b| b.mode).unwrap_or_default()
+    }
+
+    /// other actions that fire along with `action`, because one of their keybinds is part of the other
+    pub fn get_conflicts(&self, action: &Action) -> Vec<Action> {
+        let overlaps = |keys: &[Binding], other_keys: &[Binding]| {
+            !keys.is_empty() && !other_keys.is_empty()
+                && (keys.iter().all(|k| other_keys.contains(k)) || other_keys.iter().all(|k| keys.contains(k)))
+        };
+
+        Action::ALL.iter()
+            .filter(|other| *other != action)
+            .filter(|other| {
+                overlaps(self.get_bindings(action), self.get_bindings(other))
+                    || overlaps(self.get_gamepad_bindings(action), self.get_gamepad_bindings(other))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// throw away every custom keybind and go back to the defaults
+    pub fn reset_to_defaults(&mut self) -> Result<(), ConfigError> {
+        let defaults = Self::default();
+        self.bindings = defaults.bindings;
+
+        self.save()
     }
 }
 
 impl Default for ControlHandler {
-    // NOTE: When adding new controls, delete ./data/controls.dat to regenerate the file with the new controls
+    // NOTE: new actions need a keybind here, existing controls files pick it up when they are loaded
     fn default() -> Self {
         let mut bindings = HashMap::new();
         
-        bindings.insert(Action::RollLeft, vec!(Binding::Key(KeyCode::A as u16)));
-        bindings.insert(Action::RollRight, vec!(Binding::Key(KeyCode::D as u16)));
-        bindings.insert(Action::ThrottleUp, vec!(Binding::Key(KeyCode::W as u16)));
-        bindings.insert(Action::ThrottleDown, vec!(Binding::Key(KeyCode::S as u16)));
-        bindings.insert(Action::Airbrake, vec!(Binding::Key(KeyCode::LeftControl as u16)));
-
-        bindings.insert(Action::FireGun, vec!(Binding::Mouse(MouseButton::Left as u16)));
-        bindings.insert(Action::FireMissile, vec!(Binding::Key(KeyCode::Space as u16)));
+        let key = |key: KeyCode| vec!(Binding::Key(key as u16));
+        let pad = |button: Button| vec!(Binding::Gamepad(button));
+        // a little curve on the stick makes small corrections easier
+        let stick = AxisResponse { dead_zone: 0.15, exponent: 1.5 };
+        let trigger = AxisResponse { dead_zone: 0.05, exponent: 1.0 };
+        
+        bindings.insert(Action::RollLeft, Keybind::new(key(KeyCode::A), vec!(Binding::Axis(Axis::LeftStickX, false)), ActivationMode::Hold).with_response(stick));
+        bindings.insert(Action::RollRight, Keybind::new(key(KeyCode::D), vec!(Binding::Axis(Axis::LeftStickX, true)), ActivationMode::Hold).with_response(stick));
+        bindings.insert(Action::ThrottleUp, Keybind::new(key(KeyCode::W), Vec::new(), ActivationMode::Hold));
+        bindings.insert(Action::ThrottleDown, Keybind::new(key(KeyCode::S), Vec::new(), ActivationMode::Hold));
+        bindings.insert(Action::Throttle, Keybind::new(Vec::new(), pad(Button::RightTrigger2), ActivationMode::Hold).with_response(trigger));
+        bindings.insert(Action::Airbrake, Keybind::new(key(KeyCode::LeftControl), pad(Button::East), ActivationMode::Hold));
+
+        bindings.insert(Action::FireGun, Keybind::new(vec!(Binding::Mouse(MouseButton::Left as u16)), pad(Button::RightTrigger), ActivationMode::Hold));
+        bindings.insert(Action::FireMissile, Keybind::new(key(KeyCode::Space), pad(Button::LeftTrigger), ActivationMode::Hold));
         
-        bindings.insert(Action::Pause, vec!(Binding::Key(KeyCode::Escape as u16)));
+        bindings.insert(Action::Pause, Keybind::new(key(KeyCode::Escape), pad(Button::Start), ActivationMode::Press));
+        bindings.insert(Action::Scoreboard, Keybind::new(key(KeyCode::Tab), pad(Button::Select), ActivationMode::Toggle));
         
         Self {
             bindings,
+            state: ActionState::default(),
         }
     }
 }
 
+/// the throttle target after pushing it for `delta_time` seconds, `command` from -1.0 (pulling back) to 1.0 (pushing forward)
+fn move_throttle(target: f32, command: f32, max_throttle: f32, delta_time: f32) -> f32 {
+    let target = (target + command * THROTTLE_RATE * delta_time).clamp(0.0, max_throttle);
+    if command > 0.0 && target > 100.0 {
+        // past 100% the throttle jumps straight into afterburner, if the aircraft has one
+        max_throttle
+    } else if command <= 0.0 && target > 100.0 {
+        // afterburner is only held while the throttle is pushed
+        100.0
+    } else {
+        target
+    }
+}
+
+/// roll towards the mouse cursor, only as hard as needed to land on its heading this step
+fn mouse_aim_roll(player: &Player, cursor: Vec2, delta_time: f32) -> f32 {
+    let error = math::angle_difference(player.rotation, math::heading_to(player.pos, cursor));
+    // the most the aircraft can turn this step at its current speed
+    let max_turn = player.aircraft.calc_turn_rate(player.speed) * delta_time;
+    if max_turn <= 0.0 {
+        return 0.0;
+    }
+    (error / max_turn).clamp(-1.0, 1.0)
+}
+
 pub fn u16_to_keycode(key: u16) -> KeyCode {
     match key {
         0x0020 => KeyCode::Space,
@@ -335,4 +787,37 @@ pub fn u16_to_mousecode(key: u16) -> MouseButton {
         2 => MouseButton::Right,
         _ => MouseButton::Unknown,
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_one_files_are_migrated() {
+        // version 1 had no version number and only stored the keys of each action
+        let contents = r#"{"bindings": {"RollLeft": [{"Key": 81}], "NotAnAction": [{"Key": 1}]}}"#;
+        let (handler, changed) = ControlHandler::parse(contents).unwrap();
+        assert!(changed);
+
+        let defaults = ControlHandler::default();
+        let roll_left = &handler.bindings[&Action::RollLeft];
+        assert_eq!(roll_left.keys, vec![Binding::Key(KeyCode::Q as u16)]);
+        // everything the old file didn't store comes from the defaults
+        let default_roll_left = &defaults.bindings[&Action::RollLeft];
+        assert_eq!((&roll_left.gamepad, roll_left.mode, roll_left.response), (&default_roll_left.gamepad, default_roll_left.mode, default_roll_left.response));
+        assert_eq!(handler.bindings[&Action::FireGun], defaults.bindings[&Action::FireGun]);
+        assert_eq!(handler.bindings.len(), defaults.bindings.len());
+    }
+
+    #[test]
+    fn current_files_load_unchanged() {
+        let mut controls = ControlHandler::default();
+        if let Some(keybind) = controls.bindings.get_mut(&Action::Pause) {
+            keybind.keys = vec![Binding::Key(KeyCode::P as u16)];
+        }
+
+        let (loaded, changed) = ControlHandler::parse(&controls.serialize().unwrap()).unwrap();
+        assert!(!changed);
+        assert_eq!(loaded.bindings, controls.bindings);
+    }
+}
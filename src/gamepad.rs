@@ -0,0 +1,130 @@
+use std::{cell::RefCell, collections::HashSet};
+
+use gilrs::{EventType, Gilrs};
+
+pub use gilrs::{Axis, Button};
+
+use crate::{info, warn};
+
+/// every button a gamepad binding can use
+pub const BUTTONS: [Button; 19] = [
+    Button::South, Button::East, Button::North, Button::West, Button::C, Button::Z,
+    Button::LeftTrigger, Button::LeftTrigger2, Button::RightTrigger, Button::RightTrigger2,
+    Button::Select, Button::Start, Button::Mode, Button::LeftThumb, Button::RightThumb,
+    Button::DPadUp, Button::DPadDown, Button::DPadLeft, Button::DPadRight,
+];
+
+/// every axis a gamepad binding can use
+pub const AXES: [Axis; 8] = [
+    Axis::LeftStickX, Axis::LeftStickY, Axis::LeftZ,
+    Axis::RightStickX, Axis::RightStickY, Axis::RightZ,
+    Axis::DPadX, Axis::DPadY,
+];
+
+thread_local! {
+    static GAMEPADS: RefCell<Gamepads> = RefCell::new(Gamepads::new());
+}
+
+/// Every connected gamepad, read like one big gamepad the same way macroquad reads the keyboard
+struct Gamepads {
+    // None if gamepads aren't supported on this system
+    gilrs: Option<Gilrs>,
+    // buttons that went down or up since the last update
+    pressed: HashSet<Button>,
+    released: HashSet<Button>,
+}
+
+impl Gamepads {
+    fn new() -> Self {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                warn!("Gamepads are unavailable: {}", e);
+                None
+            }
+        };
+
+        Self {
+            gilrs,
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+        }
+    }
+}
+
+/// read everything the gamepads did since the last frame, called once per frame before any input is handled
+pub fn update() {
+    GAMEPADS.with_borrow_mut(|gamepads| {
+        gamepads.pressed.clear();
+        gamepads.released.clear();
+
+        let Some(gilrs) = gamepads.gilrs.as_mut() else {
+            return;
+        };
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(button, _) => {
+                    gamepads.pressed.insert(button);
+                }
+                EventType::ButtonReleased(button, _) => {
+                    gamepads.released.insert(button);
+                }
+                EventType::Connected => {
+                    info!("Gamepad connected: {}", gilrs.gamepad(event.id).name());
+                }
+                EventType::Disconnected => {
+                    info!("Gamepad disconnected: {}", gilrs.gamepad(event.id).name());
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+/// true while `button` is held on any gamepad
+pub fn is_button_down(button: Button) -> bool {
+    GAMEPADS.with_borrow(|gamepads| {
+        gamepads.gilrs.as_ref()
+            .is_some_and(|gilrs| gilrs.gamepads().any(|(_, pad)| pad.is_pressed(button)))
+    })
+}
+
+/// true on the frame `button` went down on any gamepad
+pub fn is_button_pressed(button: Button) -> bool {
+    GAMEPADS.with_borrow(|gamepads| gamepads.pressed.contains(&button))
+}
+
+/// true on the frame `button` came back up on any gamepad
+pub fn is_button_released(button: Button) -> bool {
+    GAMEPADS.with_borrow(|gamepads| gamepads.released.contains(&button))
+}
+
+/// how far `button` is pressed from 0.0 to 1.0, triggers are analog on most gamepads
+pub fn button_value(button: Button) -> f32 {
+    GAMEPADS.with_borrow(|gamepads| {
+        gamepads.gilrs.as_ref()
+            .map(|gilrs| {
+                gilrs.gamepads()
+                    .filter_map(|(_, pad)| pad.button_data(button).map(|data| data.value()))
+                    .fold(0.0, f32::max)
+            })
+            .unwrap_or(0.0)
+    })
+}
+
+/// position of `axis` from -1.0 to 1.0, taken from whichever gamepad is pushing it furthest
+pub fn axis(axis: Axis) -> f32 {
+    // gilrs won't read an unknown axis
+    if axis == Axis::Unknown {
+        return 0.0;
+    }
+    GAMEPADS.with_borrow(|gamepads| {
+        gamepads.gilrs.as_ref()
+            .map(|gilrs| {
+                gilrs.gamepads()
+                    .map(|(_, pad)| pad.value(axis))
+                    .fold(0.0, |furthest: f32, value| if value.abs() > furthest.abs() { value } else { furthest })
+            })
+            .unwrap_or(0.0)
+    })
+}
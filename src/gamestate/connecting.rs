@@ -0,0 +1,120 @@
+use std::{net::SocketAddr, rc::Rc, time::Duration};
+
+use macroquad::{color::WHITE, text::{draw_text, measure_text}, window::{screen_height, screen_width}};
+
+use crate::{assets::Assets, controls::{Action, ControlHandler}, info, net::{client::{Client, Session}, protocol::{ClientPacket, ServerPacket, PROTOCOL_VERSION}, server::ServerHandle}};
+
+use super::{disconnected::DisconnectedGS, menu::{self, Menu, MenuEvent}, multiplayer::MultiplayerGS, GameState, GameStateAction, GameStateError};
+
+// seconds between connection requests while waiting to hear back
+const CONNECT_RETRY: f32 = 0.5;
+
+/// Waits for the server to accept or reject us
+pub struct ConnectingGS {
+    assets: Rc<Assets>,
+    client: Option<Client>,
+    aircraft_id: String,
+    retry_timer: f32,
+    menu: Menu,
+    control_handler: ControlHandler,
+}
+
+impl ConnectingGS {
+    pub fn new(assets: Rc<Assets>, server: SocketAddr, aircraft_id: &str) -> Result<Box<Self>, GameStateError> {
+        let client = Client::new(server)
+            .map_err(|e| GameStateError::InitializationError(format!("Failed to initialize connection: {}", e)))?;
+        Ok(Self::with_client(assets, client, aircraft_id))
+    }
+
+    /// start a server on this machine and join it
+    pub fn host(assets: Rc<Assets>, aircraft_id: &str) -> Result<Box<Self>, GameStateError> {
+        let client = ServerHandle::host_locally(assets.aircraft(), assets.bosses())
+            .and_then(|server| {
+                info!("Hosting on {}", server.addr());
+                Client::hosting(server)
+            })
+            .map_err(|e| GameStateError::InitializationError(format!("Failed to host: {}", e)))?;
+        Ok(Self::with_client(assets, client, aircraft_id))
+    }
+
+    fn with_client(assets: Rc<Assets>, client: Client, aircraft_id: &str) -> Box<Self> {
+        info!("Connecting to {}...", client.server());
+        Box::new(Self {
+            assets,
+            client: Some(client),
+            aircraft_id: aircraft_id.to_string(),
+            retry_timer: 0.0,
+            menu: Menu::new(&["CANCEL"]),
+            control_handler: ControlHandler::load(),
+        })
+    }
+
+    fn disconnected(&mut self, reason: String) -> GameStateAction {
+        let client = self.client.take().expect("client is only taken when leaving");
+        GameStateAction::ChangeState(Box::new(DisconnectedGS::new(self.assets.clone(), &client, &self.aircraft_id, reason)))
+    }
+}
+
+impl GameState for ConnectingGS {
+
+    fn handle_input(&mut self) -> Result<GameStateAction, GameStateError> {
+        self.control_handler.update();
+        let pause = self.control_handler.was_action_activated(&Action::Pause);
+        self.control_handler.consume_presses();
+
+        match self.menu.handle_input() {
+            Some(MenuEvent::Activated(_)) | Some(MenuEvent::Back) => Ok(self.disconnected("Cancelled".to_string())),
+            _ if pause => Ok(self.disconnected("Cancelled".to_string())),
+            _ => Ok(GameStateAction::NoOp),
+        }
+    }
+
+    fn update(&mut self, delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
+        let Some(client) = self.client.as_mut() else {
+            return Ok(GameStateAction::NoOp);
+        };
+
+        for packet in client.poll() {
+            match packet {
+                ServerPacket::Welcome { player_id, bounds, tick_rate } => {
+                    info!("Connected to {} as player {}", client.server(), player_id);
+                    let client = self.client.take().expect("checked above");
+                    let session = Session::new(client, player_id, bounds.into(), tick_rate, &self.aircraft_id);
+                    let state = MultiplayerGS::new(self.assets.clone(), session);
+                    return Ok(GameStateAction::ChangeState(Box::new(state)));
+                }
+                ServerPacket::Rejected { reason } => {
+                    return Ok(self.disconnected(reason));
+                }
+                // snapshots from a previous connection can still be arriving
+                _ => {}
+            }
+        }
+
+        if client.timed_out() {
+            return Ok(self.disconnected("Could not reach the server".to_string()));
+        }
+
+        // keep asking in case the request or the reply got lost
+        self.retry_timer -= delta_time.as_secs_f32();
+        if self.retry_timer <= 0.0 {
+            self.retry_timer = CONNECT_RETRY;
+            client.send(&ClientPacket::Connect { version: PROTOCOL_VERSION, aircraft: self.aircraft_id.clone() });
+        }
+
+        Ok(GameStateAction::NoOp)
+    }
+
+    fn draw(&self, _fps: f32, _interpolation: f32) -> Result<(), GameStateError> {
+        menu::draw_background();
+        self.menu.draw("");
+
+        let server = self.client.as_ref().map(|c| c.server().to_string()).unwrap_or_default();
+        let text = format!("CONNECTING TO {}...", server);
+        let size = measure_text(&text, None, 40, 1.0);
+        draw_text(&text, (screen_width() - size.width) / 2.0, screen_height() / 2.0 - 40.0, 40.0, WHITE);
+
+        Ok(())
+    }
+
+}
@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+use macroquad::{color::{Color, RED, WHITE, YELLOW}, input::{get_keys_down, is_key_pressed, is_mouse_button_down, KeyCode, MouseButton}, shapes::draw_rectangle, text::{draw_text, measure_text}, time::get_frame_time, window::{screen_height, screen_width}};
+
+use crate::{config::ConfigError, controls::{self, Action, Binding, ControlHandler}, gamepad::{self, Button}, warn};
+
+use super::{menu::{self, Menu, MenuEvent}, GameState, GameStateAction, GameStateError};
+
+// the items after the list of actions
+const RESET: usize = Action::ALL.len();
+const BACK: usize = RESET + 1;
+// seconds of nothing being pressed before a rebind gives up
+const CAPTURE_TIMEOUT: f32 = 5.0;
+// how far a gamepad trigger or stick has to be pushed to be picked up by a rebind
+const CAPTURE_THRESHOLD: f32 = 0.5;
+
+/// A rebind in progress, built up from everything held down until something is let go
+struct Capture {
+    action: Action,
+    // inputs still held from opening the capture are ignored until they are let go
+    armed: bool,
+    held: Vec<Binding>,
+    idle: f32,
+}
+
+/// Lists every action with its keybind and lets the player change them
+pub struct ControlsGS {
+    control_handler: ControlHandler,
+    menu: Menu,
+    // the screen to go back to, whichever one opened the controls
+    previous: Option<Box<dyn GameState>>,
+    capture: Option<Capture>,
+    // why the last change couldn't be saved, cleared by the next one that is
+    save_error: Option<String>,
+}
+
+impl ControlsGS {
+    pub fn new(previous: Box<dyn GameState>) -> Self {
+        let mut labels: Vec<&str> = Action::ALL.iter().map(|_| "").collect();
+        labels.push("RESET TO DEFAULTS");
+        labels.push("BACK");
+
+        let mut state = Self {
+            control_handler: ControlHandler::load(),
+            menu: Menu::new(&labels),
+            previous: Some(previous),
+            capture: None,
+            save_error: None,
+        };
+        state.refresh_labels();
+        state
+    }
+
+    /// show each action's keybind, marking the ones that clash with another action
+    fn refresh_labels(&mut self) {
+        for (i, action) in Action::ALL.iter().enumerate() {
+            let mut keys = controls::describe_bindings(self.control_handler.get_bindings(action));
+            let gamepad = self.control_handler.get_gamepad_bindings(action);
+            if !gamepad.is_empty() {
+                keys = format!("{} / {}", keys, controls::describe_bindings(gamepad));
+            }
+            let mode = self.control_handler.get_mode(action);
+            let mut label = format!("{}: {} [{}]", action.to_string().to_uppercase(), keys.to_uppercase(), mode.to_string().to_uppercase());
+            if !self.control_handler.get_conflicts(action).is_empty() {
+                label.push_str(" (!)");
+            }
+            self.menu.set_label(i, label);
+        }
+    }
+
+    /// follow the rebind in progress, returning the finished keybind once something is let go
+    fn update_capture(capture: &mut Capture) -> Option<Vec<Binding>> {
+        let mut down: Vec<Binding> = get_keys_down().into_iter()
+            .map(|key| Binding::Key(key as u16))
+            .collect();
+        for button in [MouseButton::Left, MouseButton::Middle, MouseButton::Right] {
+            if is_mouse_button_down(button) {
+                down.push(Binding::Mouse(button as u16));
+            }
+        }
+        for button in gamepad::BUTTONS {
+            if gamepad::button_value(button) > CAPTURE_THRESHOLD {
+                down.push(Binding::Gamepad(button));
+            }
+        }
+        for axis in gamepad::AXES {
+            let value = gamepad::axis(axis);
+            if value.abs() > CAPTURE_THRESHOLD {
+                down.push(Binding::Axis(axis, value > 0.0));
+            }
+        }
+        // keys that go down on the same frame have no order, so keep them consistent
+        down.sort_by_key(|b| u16::from(*b));
+
+        if !capture.armed {
+            capture.armed = down.is_empty();
+            return None;
+        }
+
+        if capture.held.iter().any(|b| !down.contains(b)) {
+            return Some(std::mem::take(&mut capture.held));
+        }
+        for binding in down {
+            if !capture.held.contains(&binding) {
+                capture.held.push(binding);
+            }
+        }
+        None
+    }
+
+    /// the change is in effect either way, but the player should know if it won't last past this session
+    fn saved(&mut self, result: Result<(), ConfigError>) {
+        self.save_error = result.err().map(|e| {
+            warn!("{}", e);
+            e.to_string()
+        });
+        self.refresh_labels();
+    }
+
+    fn back(&mut self) -> GameStateAction {
+        match self.previous.take() {
+            Some(previous) => GameStateAction::ChangeState(previous),
+            None => GameStateAction::NoOp,
+        }
+    }
+}
+
+impl GameState for ControlsGS {
+
+    fn handle_input(&mut self) -> Result<GameStateAction, GameStateError> {
+        if let Some(capture) = self.capture.as_mut() {
+            if let Some(keys) = Self::update_capture(capture) {
+                let action = capture.action.clone();
+                self.capture = None;
+                let result = self.control_handler.edit_keybind(action, keys);
+                self.saved(result);
+            } else if capture.held.is_empty() {
+                capture.idle += get_frame_time();
+                if capture.idle >= CAPTURE_TIMEOUT {
+                    self.capture = None;
+                }
+            }
+            return Ok(GameStateAction::NoOp);
+        }
+
+        // left and right change how the selected action is triggered
+        let cycle = is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::Right)
+            || gamepad::is_button_pressed(Button::DPadLeft) || gamepad::is_button_pressed(Button::DPadRight);
+        if let Some(action) = Action::ALL.get(self.menu.selected()).filter(|_| cycle) {
+            let mode = self.control_handler.get_mode(action).next();
+            let result = self.control_handler.set_mode(action.clone(), mode);
+            self.saved(result);
+        }
+
+        match self.menu.handle_input() {
+            Some(MenuEvent::Activated(RESET)) => {
+                let result = self.control_handler.reset_to_defaults();
+                self.saved(result);
+                Ok(GameStateAction::NoOp)
+            }
+            Some(MenuEvent::Activated(BACK)) | Some(MenuEvent::Back) => Ok(self.back()),
+            Some(MenuEvent::Activated(i)) => {
+                self.capture = Some(Capture {
+                    action: Action::ALL[i].clone(),
+                    armed: false,
+                    held: Vec::new(),
+                    idle: 0.0,
+                });
+                Ok(GameStateAction::NoOp)
+            }
+            None => Ok(GameStateAction::NoOp),
+        }
+    }
+
+    fn update(&mut self, _delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
+        Ok(GameStateAction::NoOp)
+    }
+
+    fn draw(&self, _fps: f32, _interpolation: f32) -> Result<(), GameStateError> {
+        menu::draw_background();
+        self.menu.draw("CONTROLS");
+
+        let hint = "ENTER: REBIND    LEFT/RIGHT: HOLD, TOGGLE OR PRESS    ESC: BACK";
+        let size = measure_text(hint, None, 20, 1.0);
+        draw_text(hint, (screen_width() - size.width) / 2.0, screen_height() - 55.0, 20.0, WHITE);
+
+        // spell out what clashes with the selected action
+        if let Some(action) = Action::ALL.get(self.menu.selected()) {
+            let conflicts = self.control_handler.get_conflicts(action);
+            if !conflicts.is_empty() {
+                let names: Vec<String> = conflicts.iter().map(|a| a.to_string().to_uppercase()).collect();
+                let text = format!("ALSO TRIGGERS: {}", names.join(", "));
+                let size = measure_text(&text, None, 20, 1.0);
+                draw_text(&text, (screen_width() - size.width) / 2.0, screen_height() - 30.0, 20.0, RED);
+            }
+        }
+
+        if let Some(e) = &self.save_error {
+            menu::draw_notice(&format!("CONTROLS NOT SAVED, CHANGES WILL BE LOST ON EXIT ({})", e));
+        }
+
+        if let Some(capture) = &self.capture {
+            draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(0.0, 0.0, 0.0, 0.7));
+
+            let prompt = format!("PRESS A KEY, BUTTON OR COMBINATION FOR {}", capture.action.to_string().to_uppercase());
+            let size = measure_text(&prompt, None, 30, 1.0);
+            draw_text(&prompt, (screen_width() - size.width) / 2.0, screen_height() / 2.0 - 20.0, 30.0, WHITE);
+
+            let held = if capture.held.is_empty() {
+                format!("WAITING... ({}s)", (CAPTURE_TIMEOUT - capture.idle).ceil())
+            } else {
+                controls::describe_bindings(&capture.held).to_uppercase()
+            };
+            let size = measure_text(&held, None, 30, 1.0);
+            draw_text(&held, (screen_width() - size.width) / 2.0, screen_height() / 2.0 + 30.0, 30.0, YELLOW);
+        }
+
+        Ok(())
+    }
+
+}
@@ -0,0 +1,74 @@
+use std::{net::SocketAddr, rc::Rc, time::Duration};
+
+use macroquad::{color::{RED, WHITE}, text::{draw_text, measure_text}, window::{screen_height, screen_width}};
+
+use crate::{assets::Assets, info, net::client::Client};
+
+use super::{connecting::ConnectingGS, main_menu::MainMenuGS, menu::{self, Menu, MenuEvent}, GameState, GameStateAction, GameStateError};
+
+const RECONNECT: usize = 0;
+const MAIN_MENU: usize = 1;
+
+/// Shown when a connection couldn't be made or was lost, with the option to try again
+pub struct DisconnectedGS {
+    assets: Rc<Assets>,
+    server: SocketAddr,
+    // the server went down with the connection, so reconnecting means hosting it again
+    hosted: bool,
+    aircraft_id: String,
+    reason: String,
+    menu: Menu,
+}
+
+impl DisconnectedGS {
+    pub fn new(assets: Rc<Assets>, client: &Client, aircraft_id: &str, reason: String) -> Self {
+        info!("Disconnected from {}: {}", client.server(), reason);
+        Self {
+            assets,
+            server: client.server(),
+            hosted: client.is_hosting(),
+            aircraft_id: aircraft_id.to_string(),
+            reason,
+            menu: Menu::new(&["RECONNECT", "MAIN MENU"]),
+        }
+    }
+}
+
+impl GameState for DisconnectedGS {
+
+    fn handle_input(&mut self) -> Result<GameStateAction, GameStateError> {
+        match self.menu.handle_input() {
+            Some(MenuEvent::Activated(RECONNECT)) => {
+                let state = if self.hosted {
+                    ConnectingGS::host(self.assets.clone(), &self.aircraft_id)?
+                } else {
+                    ConnectingGS::new(self.assets.clone(), self.server, &self.aircraft_id)?
+                };
+                Ok(GameStateAction::ChangeState(state))
+            }
+            Some(MenuEvent::Activated(MAIN_MENU)) | Some(MenuEvent::Back) => {
+                Ok(GameStateAction::ChangeState(Box::new(MainMenuGS::new(self.assets.clone(), &self.aircraft_id))))
+            }
+            _ => Ok(GameStateAction::NoOp),
+        }
+    }
+
+    fn update(&mut self, _delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
+        Ok(GameStateAction::NoOp)
+    }
+
+    fn draw(&self, _fps: f32, _interpolation: f32) -> Result<(), GameStateError> {
+        menu::draw_background();
+        self.menu.draw("");
+
+        let title = "DISCONNECTED";
+        let size = measure_text(title, None, 60, 1.0);
+        draw_text(title, (screen_width() - size.width) / 2.0, screen_height() / 2.0 - 100.0, 60.0, RED);
+
+        let size = measure_text(&self.reason, None, 30, 1.0);
+        draw_text(&self.reason, (screen_width() - size.width) / 2.0, screen_height() / 2.0 - 50.0, 30.0, WHITE);
+
+        Ok(())
+    }
+
+}
@@ -0,0 +1,63 @@
+use std::{rc::Rc, time::Duration};
+
+use macroquad::{color::WHITE, text::{draw_text, measure_text}, window::{screen_height, screen_width}};
+
+use crate::assets::Assets;
+
+use super::{main_menu::MainMenuGS, menu::{self, Menu, MenuEvent}, playing::PlayingGS, GameState, GameStateAction, GameStateError};
+
+const RETRY: usize = 0;
+const MAIN_MENU: usize = 1;
+
+/// Shown once the player's aircraft has been destroyed, with how far they got
+pub struct GameOverGS {
+    assets: Rc<Assets>,
+    aircraft_id: String,
+    // the wave the player had reached when they were destroyed
+    wave: u32,
+    menu: Menu,
+}
+
+impl GameOverGS {
+    pub fn new(assets: Rc<Assets>, aircraft_id: &str, wave: u32) -> Self {
+        Self {
+            assets,
+            aircraft_id: aircraft_id.to_string(),
+            wave,
+            menu: Menu::new(&["RETRY MISSION", "QUIT TO MAIN MENU"]),
+        }
+    }
+}
+
+impl GameState for GameOverGS {
+
+    fn handle_input(&mut self) -> Result<GameStateAction, GameStateError> {
+        match self.menu.handle_input() {
+            Some(MenuEvent::Activated(RETRY)) => {
+                let state = PlayingGS::new(self.assets.clone(), &self.aircraft_id)?;
+                Ok(GameStateAction::ChangeState(state))
+            }
+            Some(MenuEvent::Activated(MAIN_MENU)) | Some(MenuEvent::Back) => {
+                let state = MainMenuGS::new(self.assets.clone(), &self.aircraft_id);
+                Ok(GameStateAction::ChangeState(Box::new(state)))
+            }
+            _ => Ok(GameStateAction::NoOp),
+        }
+    }
+
+    fn update(&mut self, _delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
+        Ok(GameStateAction::NoOp)
+    }
+
+    fn draw(&self, _fps: f32, _interpolation: f32) -> Result<(), GameStateError> {
+        menu::draw_background();
+        self.menu.draw("GAME OVER");
+
+        let text = format!("REACHED WAVE {}", self.wave);
+        let size = measure_text(&text, None, 30, 1.0);
+        draw_text(&text, (screen_width() - size.width) / 2.0, screen_height() / 2.0 + 130.0, 30.0, WHITE);
+
+        Ok(())
+    }
+
+}
@@ -0,0 +1,188 @@
+use std::{rc::Rc, time::Duration};
+
+use macroquad::{color::{Color, GRAY, WHITE, YELLOW}, math::{vec2, Vec2}, shapes::{draw_line, draw_rectangle, draw_rectangle_lines}, text::{draw_text, measure_text}, window::{screen_height, screen_width}};
+
+use crate::{aircraft::Aircraft, assets::{self, Assets}, settings::Settings};
+
+use super::{main_menu::MainMenuGS, menu::{self, Menu, MenuEvent}, playing::PlayingGS, GameState, GameStateAction, GameStateError};
+
+// radians per second the preview spins at
+const PREVIEW_SPIN: f32 = 0.6;
+const PREVIEW_SCALE: f32 = 2.0;
+const STAT_BAR_WIDTH: f32 = 220.0;
+// throttle percent between each sample of the thrust graph
+const THRUST_GRAPH_STEP: usize = 2;
+
+// reads one stat off an aircraft so it can be compared across the hangar
+type Stat = fn(&Aircraft) -> f32;
+
+/// Lets the player look over every airframe and pick the one they'll fly
+pub struct HangarGS {
+    assets: Rc<Assets>,
+    menu: Menu,
+    // for the units stats are shown in
+    settings: Settings,
+    preview_rotation: f32,
+    prev_preview_rotation: f32,
+}
+
+impl HangarGS {
+    /// open the hangar with `aircraft_id` selected
+    pub fn new(assets: Rc<Assets>, aircraft_id: &str) -> Self {
+        let labels: Vec<String> = assets.aircraft().iter()
+            .map(|a| a.name.to_uppercase())
+            .collect();
+        let labels: Vec<&str> = labels.iter().map(|l| l.as_str()).collect();
+        let mut menu = Menu::new(&labels).at(vec2(20.0, 100.0));
+
+        // start on the aircraft the player already had
+        let index = assets.aircraft().iter().position(|a| a.id == aircraft_id).unwrap_or(0);
+        menu.select(index);
+
+        Self {
+            assets,
+            menu,
+            settings: Settings::load(),
+            preview_rotation: 0.0,
+            prev_preview_rotation: 0.0,
+        }
+    }
+
+    fn selected(&self) -> &Aircraft {
+        &self.assets.aircraft()[self.menu.selected()]
+    }
+
+    /// a labelled bar showing `value` against the best in the hangar
+    fn draw_stat(&self, label: &str, text: String, value: f32, max: f32, x: f32, y: f32) {
+        draw_text(label, x, y, 20.0, WHITE);
+        draw_text(&text, x + 130.0 + STAT_BAR_WIDTH + 10.0, y, 20.0, WHITE);
+
+        let bar_x = x + 130.0;
+        let fill = if max > 0.0 { (value / max).clamp(0.0, 1.0) } else { 0.0 };
+        draw_rectangle(bar_x, y - 12.0, STAT_BAR_WIDTH, 12.0, Color::new(0.0, 0.0, 0.0, 0.4));
+        draw_rectangle(bar_x, y - 12.0, STAT_BAR_WIDTH * fill, 12.0, YELLOW);
+    }
+
+    fn draw_stats(&self, x: f32, y: f32) {
+        let aircraft = self.selected();
+        let roster = self.assets.aircraft();
+        let units = self.settings.units;
+        let best = |stat: Stat| roster.iter().map(stat).fold(0.0, f32::max);
+
+        let stats: [(&str, String, Stat); 7] = [
+            ("HEALTH", aircraft.max_health.to_string(), |a| a.max_health as f32),
+            ("MAX TURN", format!("{}DEG/s", aircraft.peak_turn_rate().to_degrees().round()), |a| a.peak_turn_rate()),
+            ("BASE TURN", format!("{}DEG/s", aircraft.base_turn_rate.to_degrees().round()), |a| a.base_turn_rate),
+            ("MIN TURN", format!("{}DEG/s", aircraft.min_turn_rate.to_degrees().round()), |a| a.min_turn_rate),
+            ("STALL", format!("{}{}", units.convert(aircraft.stall_speed).round(), units.suffix()), |a| a.stall_speed),
+            ("MASS", format!("{}kg", aircraft.mass.round()), |a| a.mass),
+            ("THRUST", format!("{}kN", (max_thrust(aircraft) / 1000.0).round()), max_thrust),
+        ];
+        for (i, (label, text, stat)) in stats.iter().enumerate() {
+            self.draw_stat(label, text.clone(), stat(aircraft), best(*stat), x, y + 26.0 * i as f32);
+        }
+    }
+
+    /// thrust against throttle for every aircraft, with the selected one drawn over the rest
+    fn draw_thrust_graph(&self, x: f32, y: f32, width: f32, height: f32) {
+        let roster = self.assets.aircraft();
+        let top_throttle = roster.iter().map(|a| a.max_throttle()).fold(0.0, f32::max);
+        let top_thrust = roster.iter().map(max_thrust).fold(0.0, f32::max);
+        if top_throttle <= 0.0 || top_thrust <= 0.0 {
+            return;
+        }
+
+        draw_text("THRUST CURVE", x, y - 8.0, 20.0, WHITE);
+        draw_rectangle(x, y, width, height, Color::new(0.0, 0.0, 0.0, 0.4));
+        draw_rectangle_lines(x, y, width, height, 2.0, WHITE);
+        // mark full military power so the afterburner section stands out
+        let military = x + width * 100.0 / top_throttle;
+        draw_line(military, y, military, y + height, 1.0, GRAY);
+
+        let point = |throttle: u32, aircraft: &Aircraft| {
+            let thrust = aircraft.thrust_curve.get_thrust(throttle, aircraft.thrust_multiplier);
+            vec2(x + width * throttle as f32 / top_throttle, y + height - height * thrust / top_thrust)
+        };
+        let selected = self.selected();
+        let others = roster.iter().filter(|a| a.id != selected.id);
+        for aircraft in others.chain(std::iter::once(selected)) {
+            let (color, thickness) = if aircraft.id == selected.id {
+                (YELLOW, 3.0)
+            } else {
+                (Color::new(1.0, 1.0, 1.0, 0.3), 1.5)
+            };
+            let max_throttle = aircraft.max_throttle() as u32;
+            let mut last = point(0, aircraft);
+            for throttle in (THRUST_GRAPH_STEP as u32..=max_throttle).step_by(THRUST_GRAPH_STEP) {
+                let next = point(throttle, aircraft);
+                draw_line(last.x, last.y, next.x, next.y, thickness, color);
+                last = next;
+            }
+        }
+
+        draw_text("0%", x, y + height + 18.0, 18.0, WHITE);
+        let max_label = format!("{}%", top_throttle);
+        let size = measure_text(&max_label, None, 18, 1.0);
+        draw_text(&max_label, x + width - size.width, y + height + 18.0, 18.0, WHITE);
+    }
+}
+
+/// the most thrust `aircraft` can make, at full afterburner if it has one
+fn max_thrust(aircraft: &Aircraft) -> f32 {
+    aircraft.thrust_curve.get_thrust(aircraft.max_throttle() as u32, aircraft.thrust_multiplier)
+}
+
+impl GameState for HangarGS {
+
+    fn handle_input(&mut self) -> Result<GameStateAction, GameStateError> {
+        match self.menu.handle_input() {
+            Some(MenuEvent::Activated(_)) => {
+                let state = PlayingGS::new(self.assets.clone(), &self.selected().id)?;
+                Ok(GameStateAction::ChangeState(state))
+            }
+            Some(MenuEvent::Back) => {
+                // keep the choice for the main menu's Play
+                let state = MainMenuGS::new(self.assets.clone(), &self.selected().id);
+                Ok(GameStateAction::ChangeState(Box::new(state)))
+            }
+            None => Ok(GameStateAction::NoOp),
+        }
+    }
+
+    fn update(&mut self, delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
+        self.prev_preview_rotation = self.preview_rotation;
+        self.preview_rotation += PREVIEW_SPIN * delta_time.as_secs_f32();
+        Ok(GameStateAction::NoOp)
+    }
+
+    fn draw(&self, _fps: f32, interpolation: f32) -> Result<(), GameStateError> {
+        menu::draw_background();
+        let title = "HANGAR";
+        let size = measure_text(title, None, 60, 1.0);
+        draw_text(title, (screen_width() - size.width) / 2.0, 60.0, 60.0, WHITE);
+        self.menu.draw("");
+
+        let aircraft = self.selected();
+
+        // the selected aircraft turning slowly in the middle of the screen
+        let sprite = self.assets.require_texture(&aircraft.sprite)?;
+        let rotation = self.prev_preview_rotation + (self.preview_rotation - self.prev_preview_rotation) * interpolation;
+        let center = vec2(screen_width() * 0.45, screen_height() * 0.4);
+        assets::draw_scaled(sprite, center, rotation, PREVIEW_SCALE);
+
+        let size = measure_text(&aircraft.description, None, 20, 1.0);
+        let description_pos = Vec2::new(center.x - size.width / 2.0, screen_height() * 0.75);
+        draw_text(&aircraft.description, description_pos.x, description_pos.y, 20.0, WHITE);
+
+        let stats_x = screen_width() - 480.0;
+        self.draw_stats(stats_x, 110.0);
+        self.draw_thrust_graph(stats_x, 330.0, 440.0, 180.0);
+
+        let hint = "ENTER: FLY    ESC: BACK";
+        let size = measure_text(hint, None, 20, 1.0);
+        draw_text(hint, (screen_width() - size.width) / 2.0, screen_height() - 30.0, 20.0, WHITE);
+
+        Ok(())
+    }
+
+}
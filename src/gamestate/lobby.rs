@@ -0,0 +1,142 @@
+use std::{net::{Ipv4Addr, SocketAddr}, rc::Rc, time::Duration};
+
+use macroquad::{color::{WHITE, YELLOW}, input::{get_char_pressed, is_key_pressed, KeyCode}, text::{draw_text, measure_text}, window::{screen_height, screen_width}};
+
+use crate::{assets::Assets, net::DEFAULT_PORT, warn};
+
+use super::{connecting::ConnectingGS, main_menu::MainMenuGS, menu::{self, Menu, MenuEvent}, GameState, GameStateAction, GameStateError};
+
+const HOST: usize = 0;
+const ADDRESS: usize = 1;
+const JOIN: usize = 2;
+const BACK: usize = 3;
+
+// long enough for any ipv6 address and port
+const MAX_ADDRESS_LENGTH: usize = 47;
+
+/// Host a game on this machine or join someone else's
+pub struct LobbyGS {
+    assets: Rc<Assets>,
+    aircraft_id: String,
+    menu: Menu,
+    // the server to join, as typed
+    address: String,
+    // typing goes into the address until enter or escape
+    editing: bool,
+    error: Option<String>,
+}
+
+impl LobbyGS {
+    pub fn new(assets: Rc<Assets>, aircraft_id: &str) -> Self {
+        let mut state = Self {
+            assets,
+            aircraft_id: aircraft_id.to_string(),
+            menu: Menu::new(&["HOST GAME", "", "JOIN GAME", "BACK"]),
+            address: SocketAddr::from((Ipv4Addr::LOCALHOST, DEFAULT_PORT)).to_string(),
+            editing: false,
+            error: None,
+        };
+        state.refresh_labels();
+        state
+    }
+
+    fn refresh_labels(&mut self) {
+        let cursor = if self.editing { "_" } else { "" };
+        self.menu.set_label(ADDRESS, format!("ADDRESS: {}{}", self.address, cursor));
+    }
+
+    /// follow the typing into the address, returning true once it is finished
+    fn edit_address(&mut self) -> bool {
+        while let Some(c) = get_char_pressed() {
+            let allowed = c.is_ascii_hexdigit() || matches!(c, '.' | ':' | '[' | ']');
+            if allowed && self.address.len() < MAX_ADDRESS_LENGTH {
+                self.address.push(c);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.address.pop();
+        }
+        is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Escape)
+    }
+
+    fn join(&mut self) -> GameStateAction {
+        match self.address.parse::<SocketAddr>() {
+            Ok(addr) => self.connect(ConnectingGS::new(self.assets.clone(), addr, &self.aircraft_id)),
+            Err(_) => {
+                self.error = Some(format!("NOT AN ADDRESS, I.E. 127.0.0.1:{}", DEFAULT_PORT));
+                GameStateAction::NoOp
+            }
+        }
+    }
+
+    // a server that can't be started or reached shouldn't take the game down with it
+    fn connect(&mut self, connecting: Result<Box<ConnectingGS>, GameStateError>) -> GameStateAction {
+        match connecting {
+            Ok(state) => GameStateAction::ChangeState(state),
+            Err(e) => {
+                warn!("{}", e);
+                self.error = Some(e.to_string().to_uppercase());
+                GameStateAction::NoOp
+            }
+        }
+    }
+
+    fn back(&self) -> GameStateAction {
+        GameStateAction::ChangeState(Box::new(MainMenuGS::new(self.assets.clone(), &self.aircraft_id)))
+    }
+}
+
+impl GameState for LobbyGS {
+
+    fn handle_input(&mut self) -> Result<GameStateAction, GameStateError> {
+        if self.editing {
+            if self.edit_address() {
+                self.editing = false;
+            }
+            self.refresh_labels();
+            return Ok(GameStateAction::NoOp);
+        }
+
+        match self.menu.handle_input() {
+            Some(MenuEvent::Activated(HOST)) => {
+                let connecting = ConnectingGS::host(self.assets.clone(), &self.aircraft_id);
+                Ok(self.connect(connecting))
+            }
+            Some(MenuEvent::Activated(ADDRESS)) => {
+                // throw away anything typed before the address was picked
+                while get_char_pressed().is_some() {}
+                self.editing = true;
+                self.error = None;
+                self.refresh_labels();
+                Ok(GameStateAction::NoOp)
+            }
+            Some(MenuEvent::Activated(JOIN)) => Ok(self.join()),
+            Some(MenuEvent::Activated(BACK)) | Some(MenuEvent::Back) => Ok(self.back()),
+            _ => Ok(GameStateAction::NoOp),
+        }
+    }
+
+    fn update(&mut self, _delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
+        Ok(GameStateAction::NoOp)
+    }
+
+    fn draw(&self, _fps: f32, _interpolation: f32) -> Result<(), GameStateError> {
+        menu::draw_background();
+        self.menu.draw("MULTIPLAYER");
+
+        let hint = if self.editing {
+            "TYPE THE SERVER ADDRESS    ENTER: DONE"
+        } else {
+            "HOSTED GAMES ARE ONLY REACHABLE FROM THIS MACHINE"
+        };
+        let size = measure_text(hint, None, 20, 1.0);
+        draw_text(hint, (screen_width() - size.width) / 2.0, screen_height() - 30.0, 20.0, if self.editing { YELLOW } else { WHITE });
+
+        if let Some(e) = &self.error {
+            menu::draw_notice(e);
+        }
+
+        Ok(())
+    }
+
+}
@@ -0,0 +1,84 @@
+use std::{rc::Rc, time::Duration};
+
+use macroquad::{color::WHITE, text::{draw_text, measure_text}, window::{screen_height, screen_width}};
+
+use crate::assets::Assets;
+
+use super::{controls::ControlsGS, hangar::HangarGS, lobby::LobbyGS, menu::{self, Menu, MenuEvent}, playing::PlayingGS, settings::SettingsGS, GameState, GameStateAction, GameStateError};
+
+const PLAY: usize = 0;
+const MULTIPLAYER: usize = 1;
+const HANGAR: usize = 2;
+const SETTINGS: usize = 3;
+const CONTROLS: usize = 4;
+const QUIT: usize = 5;
+
+/// The first thing the player sees, and where they come back to after a game
+pub struct MainMenuGS {
+    assets: Rc<Assets>,
+    menu: Menu,
+    // the aircraft Play will put the player in
+    aircraft_id: String,
+}
+
+impl MainMenuGS {
+    pub fn new(assets: Rc<Assets>, aircraft_id: &str) -> Self {
+        let menu = Menu::new(&["PLAY", "MULTIPLAYER", "HANGAR", "SETTINGS", "CONTROLS", "QUIT"]);
+
+        Self {
+            assets,
+            menu,
+            aircraft_id: aircraft_id.to_string(),
+        }
+    }
+}
+
+impl GameState for MainMenuGS {
+
+    fn handle_input(&mut self) -> Result<GameStateAction, GameStateError> {
+        match self.menu.handle_input() {
+            Some(MenuEvent::Activated(PLAY)) => {
+                let state = PlayingGS::new(self.assets.clone(), &self.aircraft_id)?;
+                Ok(GameStateAction::ChangeState(state))
+            }
+            Some(MenuEvent::Activated(MULTIPLAYER)) => {
+                let state = LobbyGS::new(self.assets.clone(), &self.aircraft_id);
+                Ok(GameStateAction::ChangeState(Box::new(state)))
+            }
+            Some(MenuEvent::Activated(HANGAR)) => {
+                let state = HangarGS::new(self.assets.clone(), &self.aircraft_id);
+                Ok(GameStateAction::ChangeState(Box::new(state)))
+            }
+            Some(MenuEvent::Activated(SETTINGS)) => {
+                let back = MainMenuGS::new(self.assets.clone(), &self.aircraft_id);
+                Ok(GameStateAction::ChangeState(Box::new(SettingsGS::new(Box::new(back)))))
+            }
+            Some(MenuEvent::Activated(CONTROLS)) => {
+                let back = MainMenuGS::new(self.assets.clone(), &self.aircraft_id);
+                Ok(GameStateAction::ChangeState(Box::new(ControlsGS::new(Box::new(back)))))
+            }
+            Some(MenuEvent::Activated(QUIT)) => Ok(GameStateAction::Exit),
+            _ => Ok(GameStateAction::NoOp),
+        }
+    }
+
+    fn update(&mut self, _delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
+        Ok(GameStateAction::NoOp)
+    }
+
+    fn draw(&self, _fps: f32, _interpolation: f32) -> Result<(), GameStateError> {
+        menu::draw_background();
+        self.menu.draw("MERLIN");
+
+        // remind the player what they'll be flying
+        let aircraft = self.assets.get_aircraft(&self.aircraft_id)
+            .map(|a| a.name.to_uppercase())
+            .unwrap_or_default();
+        let text = format!("AIRCRAFT: {}", aircraft);
+        let size = measure_text(&text, None, 20, 1.0);
+        draw_text(&text, (screen_width() - size.width) / 2.0, screen_height() - 30.0, 20.0, WHITE);
+
+        Ok(())
+    }
+
+}
@@ -0,0 +1,196 @@
+use macroquad::{color::{Color, DARKGRAY, RED, WHITE, YELLOW}, input::{is_key_pressed, is_mouse_button_pressed, mouse_position, KeyCode, MouseButton}, math::{vec2, Rect, Vec2}, shapes::draw_rectangle, text::{draw_text, measure_text}, window::{clear_background, screen_height, screen_width}};
+
+use crate::gamepad::{self, Axis, Button};
+
+const ITEM_HEIGHT: f32 = 44.0;
+pub const ITEM_WIDTH: f32 = 320.0;
+const FONT_SIZE: u16 = 32;
+// how far the stick has to be pushed to move the selection
+const STICK_THRESHOLD: f32 = 0.5;
+
+pub struct MenuItem {
+    pub label: String,
+    // disabled items are drawn greyed out and skipped over
+    pub enabled: bool,
+}
+
+pub enum MenuEvent {
+    // the item at this index was chosen
+    Activated(usize),
+    // the player wants to leave the menu
+    Back,
+}
+
+/// A vertical list of items navigated with the keyboard, mouse or a gamepad
+pub struct Menu {
+    items: Vec<MenuItem>,
+    selected: usize,
+    // the mouse only changes the selection when it moves, so it doesn't fight the keyboard
+    last_mouse: Vec2,
+    // the stick has to come back to center before it moves the selection again
+    stick_latched: bool,
+    // top left of the first item, centered on the screen if not set
+    origin: Option<Vec2>,
+}
+
+impl Menu {
+    pub fn new(labels: &[&str]) -> Self {
+        Self {
+            items: labels.iter()
+                .map(|label| MenuItem { label: label.to_string(), enabled: true })
+                .collect(),
+            selected: 0,
+            last_mouse: Vec2::from(mouse_position()),
+            stick_latched: false,
+            origin: None,
+        }
+    }
+
+    /// lay the items out from `origin` instead of centering them on the screen
+    pub fn at(mut self, origin: Vec2) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        if let Some(item) = self.items.get_mut(index) {
+            item.enabled = enabled;
+        }
+        if !enabled && self.selected == index {
+            self.step(1);
+        }
+    }
+
+    pub fn set_label(&mut self, index: usize, label: String) {
+        if let Some(item) = self.items.get_mut(index) {
+            item.label = label;
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// select the item at `index` if it exists and is enabled
+    pub fn select(&mut self, index: usize) {
+        if self.items.get(index).is_some_and(|item| item.enabled) {
+            self.selected = index;
+        }
+    }
+
+    /// move the selection by `direction` items, skipping disabled ones and wrapping at the ends
+    fn step(&mut self, direction: isize) {
+        let len = self.items.len() as isize;
+        let mut index = self.selected as isize;
+        for _ in 0..len {
+            index = (index + direction).rem_euclid(len);
+            if self.items[index as usize].enabled {
+                self.selected = index as usize;
+                return;
+            }
+        }
+    }
+
+    /// where the item at `index` is drawn, which is also where it can be clicked
+    fn item_rect(&self, index: usize) -> Rect {
+        let origin = self.origin.unwrap_or_else(|| {
+            let total = ITEM_HEIGHT * self.items.len() as f32;
+            vec2((screen_width() - ITEM_WIDTH) / 2.0, (screen_height() - total) / 2.0 + 40.0)
+        });
+        Rect::new(origin.x, origin.y + ITEM_HEIGHT * index as f32, ITEM_WIDTH, ITEM_HEIGHT)
+    }
+
+    pub fn handle_input(&mut self) -> Option<MenuEvent> {
+        // keyboard
+        if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
+            self.step(-1);
+        }
+        if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) {
+            self.step(1);
+        }
+
+        // gamepad, the stick acts like a d-pad press each time it is pushed
+        if gamepad::is_button_pressed(Button::DPadUp) {
+            self.step(-1);
+        }
+        if gamepad::is_button_pressed(Button::DPadDown) {
+            self.step(1);
+        }
+        let stick = gamepad::axis(Axis::LeftStickY);
+        if stick.abs() < STICK_THRESHOLD {
+            self.stick_latched = false;
+        } else if !self.stick_latched {
+            self.stick_latched = true;
+            // up is positive on the stick but moves up the list
+            self.step(if stick > 0.0 { -1 } else { 1 });
+        }
+
+        // mouse
+        let mouse = Vec2::from(mouse_position());
+        let hovered = (0..self.items.len())
+            .find(|i| self.items[*i].enabled && self.item_rect(*i).contains(mouse));
+        if mouse != self.last_mouse {
+            self.last_mouse = mouse;
+            if let Some(hovered) = hovered {
+                self.selected = hovered;
+            }
+        }
+        if is_mouse_button_pressed(MouseButton::Left) {
+            if let Some(hovered) = hovered {
+                self.selected = hovered;
+                return Some(MenuEvent::Activated(hovered));
+            }
+        }
+
+        let activated = is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space)
+            || gamepad::is_button_pressed(Button::South);
+        if activated && self.items.get(self.selected).is_some_and(|item| item.enabled) {
+            return Some(MenuEvent::Activated(self.selected));
+        }
+
+        let back = is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Backspace)
+            || gamepad::is_button_pressed(Button::East);
+        if back {
+            return Some(MenuEvent::Back);
+        }
+
+        None
+    }
+
+    /// draw the menu with `title` above it, on top of whatever is already on screen
+    pub fn draw(&self, title: &str) {
+        if !title.is_empty() {
+            let size = measure_text(title, None, 60, 1.0);
+            let title_y = self.item_rect(0).y - 40.0;
+            draw_text(title, (screen_width() - size.width) / 2.0, title_y, 60.0, WHITE);
+        }
+
+        for (i, item) in self.items.iter().enumerate() {
+            let rect = self.item_rect(i);
+            let color = if !item.enabled {
+                DARKGRAY
+            } else if i == self.selected {
+                YELLOW
+            } else {
+                WHITE
+            };
+            let label = if i == self.selected { format!("> {} <", item.label) } else { item.label.clone() };
+            let size = measure_text(&label, None, FONT_SIZE, 1.0);
+            let pos = vec2(rect.x + (rect.w - size.width) / 2.0, rect.y + (rect.h + size.offset_y) / 2.0);
+            draw_text(&label, pos.x, pos.y, FONT_SIZE as f32, color);
+        }
+    }
+}
+
+/// the plain background menus are drawn over when there is no game behind them
+pub fn draw_background() {
+    clear_background(Color::from_rgba(11, 156, 209, 255));
+}
+
+/// a line across the top of the screen for something the player needs to know about, like a failed save or a server that couldn't be reached
+pub fn draw_notice(text: &str) {
+    let size = measure_text(text, None, 24, 1.0);
+    let x = (screen_width() - size.width) / 2.0;
+    draw_rectangle(x - 10.0, 8.0, size.width + 20.0, 30.0, Color::new(0.0, 0.0, 0.0, 0.6));
+    draw_text(text, x, 30.0, 24.0, RED);
+}
@@ -1,17 +1,31 @@
 use std::{fmt::{self, Display, Formatter}, time::Duration};
 
+pub mod main_menu;
+pub mod lobby;
+pub mod hangar;
+pub mod controls;
+pub mod settings;
+pub mod menu;
 pub mod playing;
 pub mod pause;
+pub mod render;
+pub mod connecting;
+pub mod multiplayer;
+pub mod disconnected;
+pub mod results;
+pub mod game_over;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum GameStateError {
     InitializationError(String),
+    MissingAsset(String),
 }
 
 impl Display for GameStateError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             GameStateError::InitializationError(e) => write!(f, "InitializationError: {}", e),
+            GameStateError::MissingAsset(e) => write!(f, "MissingAsset: {}", e),
         }
     }
 }
@@ -24,7 +38,13 @@ pub enum GameStateAction {
 
 pub trait GameState {
 
+    /// called once per rendered frame before any simulation steps, input and menu navigation belong here
+    fn handle_input(&mut self) -> Result<GameStateAction, GameStateError> {
+        Ok(GameStateAction::NoOp)
+    }
+    /// called at a fixed rate, `delta_time` is always `SIMULATION_TIMESTEP`
     fn update(&mut self, delta_time: &Duration) -> Result<GameStateAction, GameStateError>;
-    fn draw(&self, fps: f32) -> Result<(), GameStateError>;
+    /// `interpolation` is how far (0.0 - 1.0) the current frame is between the last simulation step and the next
+    fn draw(&self, fps: f32, interpolation: f32) -> Result<(), GameStateError>;
 
 }
\ No newline at end of file
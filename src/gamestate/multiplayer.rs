@@ -0,0 +1,160 @@
+use std::{rc::Rc, time::Duration};
+
+use macroquad::{camera::{set_camera, set_default_camera}, color::WHITE};
+
+use crate::{assets::Assets, audio::LockTone, controls::{Action, ControlHandler}, net::{client::Session, protocol::ServerPacket}, player::{Player, PlayerInput}, pvp::MatchStatus, settings::{Settings, SteeringMode}, waves::WaveStatus, world::World, SIMULATION_TIMESTEP};
+
+use super::{disconnected::DisconnectedGS, render, results::ResultsGS, GameState, GameStateAction, GameStateError};
+
+/// Flies our aircraft in a world simulated by a server, drawing whatever it last sent us
+pub struct MultiplayerGS {
+    assets: Rc<Assets>,
+    session: Option<Session>,
+    // the latest snapshot from the server, with the one before it kept in the aircraft for interpolation
+    world: World,
+    waves: Option<WaveStatus>,
+    pvp: Option<MatchStatus>,
+    last_snapshot: u64,
+    // seconds since the latest snapshot arrived
+    snapshot_age: f32,
+    control_handler: ControlHandler,
+    // the last input sent, the server only hears what we ask for so the throttle target is kept here
+    input: PlayerInput,
+    settings: Settings,
+    lock_tone: LockTone,
+}
+
+impl MultiplayerGS {
+    pub fn new(assets: Rc<Assets>, session: Session) -> Self {
+        Self {
+            assets,
+            world: World::new(session.bounds),
+            session: Some(session),
+            waves: None,
+            pvp: None,
+            last_snapshot: 0,
+            snapshot_age: 0.0,
+            control_handler: ControlHandler::load(),
+            input: PlayerInput::default(),
+            settings: Settings::load(),
+            lock_tone: LockTone::Silent,
+        }
+    }
+
+    fn player_id(&self) -> u32 {
+        self.session.as_ref().map(|s| s.player_id).unwrap_or_default()
+    }
+
+    fn player(&self) -> Option<&Player> {
+        self.world.player(self.player_id())
+    }
+
+    fn silence(&mut self) {
+        self.lock_tone.stop(self.assets.sounds());
+        self.lock_tone = LockTone::Silent;
+    }
+
+    fn disconnected(&mut self, reason: String) -> GameStateAction {
+        self.silence();
+        let session = self.session.take().expect("session is only taken when leaving");
+        GameStateAction::ChangeState(Box::new(DisconnectedGS::new(self.assets.clone(), &session.client, &session.aircraft_id, reason)))
+    }
+}
+
+impl GameState for MultiplayerGS {
+
+    fn handle_input(&mut self) -> Result<GameStateAction, GameStateError> {
+        // active actions are sent to the server every step
+        self.control_handler.update();
+
+        // the server doesn't stop for anyone, so pausing leaves the game
+        if self.control_handler.was_action_activated(&Action::Pause) {
+            return Ok(self.disconnected("You left the game".to_string()));
+        }
+
+        let tone = self.player().map(LockTone::for_player).unwrap_or(LockTone::Silent);
+        self.lock_tone = self.lock_tone.transition(tone, self.assets.sounds(), self.settings.sfx_volume());
+
+        Ok(GameStateAction::NoOp)
+    }
+
+    fn update(&mut self, delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
+        let Some(session) = self.session.as_mut() else {
+            return Ok(GameStateAction::NoOp);
+        };
+
+        self.snapshot_age += delta_time.as_secs_f32();
+        for packet in session.client.poll() {
+            match packet {
+                // snapshots can arrive out of order, only ever move forwards
+                ServerPacket::Snapshot(snapshot) if snapshot.tick > self.last_snapshot => {
+                    let elapsed = (snapshot.tick - self.last_snapshot) as f32 * session.server_timestep();
+                    snapshot.apply(&mut self.world, self.assets.aircraft(), self.assets.bosses(), elapsed);
+                    self.waves = snapshot.waves;
+                    self.pvp = snapshot.pvp;
+                    self.last_snapshot = snapshot.tick;
+                    self.snapshot_age = 0.0;
+                }
+                ServerPacket::Disconnect { reason } => {
+                    return Ok(self.disconnected(reason));
+                }
+                _ => {}
+            }
+        }
+
+        if session.client.timed_out() {
+            return Ok(self.disconnected("Connection timed out".to_string()));
+        }
+
+        // keep sending the last input while there is no aircraft to fly
+        if let Some(player) = self.world.player(session.player_id) {
+            let cursor = (self.settings.steering == SteeringMode::MouseAim).then(|| render::cursor(self.world.bounds));
+            self.input = self.control_handler.player_input(&self.input, player, cursor, delta_time.as_secs_f32());
+        }
+        session.send_input(self.input.clone());
+        self.control_handler.consume_presses();
+
+        // show the results once the match is over, the results screen takes over the connection
+        if let Some(status) = self.pvp.take_if(|status| status.is_finished()) {
+            self.silence();
+            let session = self.session.take().expect("checked above");
+            return Ok(GameStateAction::ChangeState(Box::new(ResultsGS::new(self.assets.clone(), session, status))));
+        }
+
+        Ok(GameStateAction::NoOp)
+    }
+
+    fn draw(&self, fps: f32, interpolation: f32) -> Result<(), GameStateError> {
+        // remote aircraft move from their previous snapshot position to the latest one over a server step
+        let server_timestep = self.session.as_ref().map(|s| s.server_timestep()).unwrap_or(SIMULATION_TIMESTEP.as_secs_f32());
+        let age = self.snapshot_age + interpolation * SIMULATION_TIMESTEP.as_secs_f32();
+        let interpolation = (age / server_timestep).min(1.0);
+
+        // the server's world is a fixed size, scale it to fit the window
+        set_camera(&render::world_view(self.world.bounds));
+        render::draw_world(&self.assets, &self.world, interpolation)?;
+
+        let Some(player) = self.player() else {
+            set_default_camera();
+            render::draw_banner("WAITING FOR SERVER", WHITE);
+            return Ok(());
+        };
+
+        render::draw_seeker(&self.world, player, interpolation);
+        if self.settings.steering == SteeringMode::MouseAim {
+            render::draw_mouse_aim(player, render::cursor(self.world.bounds), interpolation);
+        }
+        set_default_camera();
+        render::draw_hud(&self.world, player, self.waves.as_ref(), fps, &self.settings);
+
+        if let Some(status) = &self.pvp {
+            render::draw_match_hud(status, player);
+            if self.control_handler.is_action_active(&Action::Scoreboard) {
+                render::draw_scoreboard(status, player.id, "SCOREBOARD");
+            }
+        }
+
+        Ok(())
+    }
+
+}
@@ -2,49 +2,88 @@ use std::time::Duration;
 
 use macroquad::{color::Color, shapes::draw_rectangle, window::{screen_height, screen_width}};
 
-use crate::controls::ControlHandler;
+use crate::controls::{Action, ControlHandler};
 
-use super::{playing::PlayingGS, GameState, GameStateAction, GameStateError};
+use super::{controls::ControlsGS, main_menu::MainMenuGS, menu::{Menu, MenuEvent}, playing::PlayingGS, settings::SettingsGS, GameState, GameStateAction, GameStateError};
+
+const RESUME: usize = 0;
+const RESTART: usize = 1;
+const CONTROLS: usize = 2;
+const SETTINGS: usize = 3;
+const MAIN_MENU: usize = 4;
+const QUIT: usize = 5;
 
 pub struct PauseGS {
     previous_play_state: PlayingGS,
+    menu: Menu,
+    // loaded on the first frame here, so it has any changes made in the controls screen
+    control_handler: Option<ControlHandler>,
 }
 
 impl PauseGS {
     pub fn new(previous_play_state: PlayingGS) -> Self {
+        let menu = Menu::new(&["RESUME", "RESTART MISSION", "CONTROLS", "SETTINGS", "QUIT TO MAIN MENU", "QUIT GAME"]);
+
         Self {
             previous_play_state,
+            menu,
+            control_handler: None,
         }
     }
+
+    fn resume(&mut self) -> GameStateAction {
+        self.previous_play_state.reload_settings();
+        GameStateAction::ChangeState(Box::new(self.previous_play_state.clone()))
+    }
 }
 
 impl GameState for PauseGS {
 
-    fn update(&mut self, _delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
-        let control_handler = ControlHandler::load();
-        // handle on release to ensure pause key isnt spammed when held (was an issue)
-        let actions = control_handler.get_actions_up();
-        for action in actions {
-            match action {
-                crate::controls::Action::Pause => {
-                    self.previous_play_state.reload_controls();
-                    return Ok(GameStateAction::ChangeState(Box::new(self.previous_play_state.clone())));
-                }
-                _ => {}
+    fn handle_input(&mut self) -> Result<GameStateAction, GameStateError> {
+        let control_handler = self.control_handler.get_or_insert_with(ControlHandler::load);
+        control_handler.update();
+        // a pause key still held from before doesn't count, it has to be pressed again here
+        if control_handler.was_action_activated(&Action::Pause) {
+            return Ok(self.resume());
+        }
+
+        match self.menu.handle_input() {
+            Some(MenuEvent::Activated(RESUME)) => Ok(self.resume()),
+            Some(MenuEvent::Activated(RESTART)) => Ok(GameStateAction::ChangeState(self.previous_play_state.restart()?)),
+            Some(MenuEvent::Activated(CONTROLS)) => {
+                let paused = PauseGS::new(self.previous_play_state.clone());
+                Ok(GameStateAction::ChangeState(Box::new(ControlsGS::new(Box::new(paused)))))
             }
+            Some(MenuEvent::Activated(SETTINGS)) => {
+                let paused = PauseGS::new(self.previous_play_state.clone());
+                Ok(GameStateAction::ChangeState(Box::new(SettingsGS::new(Box::new(paused)))))
+            }
+            Some(MenuEvent::Activated(MAIN_MENU)) => {
+                let play = &self.previous_play_state;
+                let state = MainMenuGS::new(play.assets().clone(), play.aircraft_id());
+                Ok(GameStateAction::ChangeState(Box::new(state)))
+            }
+            Some(MenuEvent::Activated(QUIT)) => Ok(GameStateAction::Exit),
+            // leaving is left to the pause key above, which can be bound to more than escape
+            _ => Ok(GameStateAction::NoOp),
         }
+    }
+
+    fn update(&mut self, _delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
+        // the simulation is frozen while paused
         Ok(GameStateAction::NoOp)
     }
 
-    fn draw(&self, fps: f32) -> Result<(), GameStateError> {
-        // draw the player in the correct position
-        // todo: this will not show updates to other players surrounding the player when networking is implemented, the update function may need to be called with a special pause flag?
-        self.previous_play_state.draw(fps)?;
+    fn draw(&self, fps: f32, _interpolation: f32) -> Result<(), GameStateError> {
+        // the play state hasn't stepped since pausing, so draw it fully at its latest step
+        self.previous_play_state.draw(fps, 1.0)?;
 
         // draw a semi-transparent overlay
         draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(0.0, 0.0, 0.0, 0.5));
 
+        self.menu.draw("PAUSED");
+
         Ok(())
     }
     
-}
\ No newline at end of file
+}
@@ -1,167 +1,143 @@
-use std::time::Duration;
+use std::{rc::Rc, time::Duration};
 
-use macroquad::{color::{Color, BLACK, WHITE}, text::draw_text, texture::{draw_texture_ex, DrawTextureParams}, window::clear_background};
+use macroquad::{camera::{set_camera, set_default_camera}, math::vec2, window::{screen_height, screen_width}};
 
-use crate::{controls::{Action, ControlHandler}, player::{self, PlayerState}};
+use crate::{assets::Assets, audio::LockTone, controls::{Action, ControlHandler}, player::Player, settings::{Settings, SteeringMode}, waves::{WaveDirector, WaveTable, WAVES_PATH}, world::{World, PLAYER_TEAM}};
 
-use super::{GameState, GameStateAction, GameStateError};
+use super::{game_over::GameOverGS, render, GameState, GameStateAction, GameStateError};
 
-pub const THROTTLE_INCREMENTATION: f32 = 1.0;
-
-// TODO: make this a setting
-pub const AIRBRAKE_TOGGLE: bool = false;
+// seconds the wreck is shown before the game over screen
+const GAME_OVER_DELAY: f32 = 2.0;
 
 #[derive(Clone)]
 pub struct PlayingGS {
-    player: player::Player,
+    assets: Rc<Assets>,
+    world: World,
+    waves: WaveDirector,
+    // id of the aircraft this client is flying
+    player_id: u32,
+    // seconds since the player was destroyed
+    destroyed_for: f32,
     control_handler: ControlHandler,
+    settings: Settings,
+    lock_tone: LockTone,
 }
 
 impl PlayingGS {
-    pub async fn new() -> Result<Box<Self>, GameStateError> {
-        let player = player::Player::new().await;
-        if let Err(e) = player {
-            return Err(GameStateError::InitializationError(format!("Failed to initialize player: {}", e)));
+    pub fn new(assets: Rc<Assets>, aircraft_id: &str) -> Result<Box<Self>, GameStateError> {
+        let aircraft = assets.get_aircraft(aircraft_id);
+        if aircraft.is_none() {
+            return Err(GameStateError::InitializationError(format!("Failed to initialize player: unknown aircraft '{}'", aircraft_id)));
         }
-        let player = player.unwrap();
+        let bounds = vec2(screen_width(), screen_height());
+        let mut world = World::new(bounds);
+        let player_id = world.spawn_player(aircraft.unwrap().clone(), PLAYER_TEAM, bounds / 2.0, 0.0);
+
+        let waves = WaveTable::load(WAVES_PATH)
+            .and_then(|table| WaveDirector::new(table, assets.aircraft(), assets.bosses()))
+            .map_err(|e| GameStateError::InitializationError(format!("Failed to initialize waves: {}", e)))?;
 
         let control_handler = ControlHandler::load();
 
         Ok(Box::new(Self {
-            player,
+            assets,
+            world,
+            waves,
+            player_id,
+            destroyed_for: 0.0,
             control_handler,
+            settings: Settings::load(),
+            lock_tone: LockTone::Silent,
         }))
     }
 
-    pub fn reload_controls(&mut self) {
-        self.control_handler = ControlHandler::load();
+    /// pick up anything changed from the pause menu before flying again
+    pub fn reload_settings(&mut self) {
+        self.control_handler.reload();
+        self.settings = Settings::load();
+    }
+
+    /// a fresh mission in the same aircraft
+    pub fn restart(&self) -> Result<Box<Self>, GameStateError> {
+        Self::new(self.assets.clone(), self.aircraft_id())
+    }
+
+    pub fn assets(&self) -> &Rc<Assets> {
+        &self.assets
+    }
+
+    pub fn aircraft_id(&self) -> &str {
+        &self.player().aircraft.id
+    }
+
+    fn player(&self) -> &Player {
+        // the local player is never removed from the world
+        self.world.player(self.player_id).expect("local player missing from world")
     }
 }
 
 impl GameState for PlayingGS {
 
-    fn update(&mut self, delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
+    fn handle_input(&mut self) -> Result<GameStateAction, GameStateError> {
+        // active actions are applied by the simulation steps
+        self.control_handler.update();
 
-        // handle input and make the player respond accordingly
-        let actions = self.control_handler.get_actions_down();
-        // handle various movement types
-        for action in actions {
-            match action {
-                Action::ThrottleUp => {
-                    self.player.set_throttle(self.player.throttle_percent + THROTTLE_INCREMENTATION);
-                    if self.player.throttle_percent > 100.0 {
-                        self.player.set_throttle(110.0);
-                    }
-                }
-                Action::ThrottleDown => {
-                    self.player.set_throttle(self.player.throttle_percent - THROTTLE_INCREMENTATION);
-                    if self.player.throttle_percent < 0.0 {
-                        self.player.set_throttle(0.0);
-                    }
-                }
-                Action::RollLeft => {
-                    if self.player.state == PlayerState::TurningRight {
-                        self.player.state = PlayerState::Normal;
-                    } else {
-                        self.player.apply_action(PlayerState::TurningLeft);
-                    }
-                }
-                Action::RollRight => {
-                    if self.player.state == PlayerState::TurningLeft {
-                        self.player.state = PlayerState::Normal;
-                    } else {
-                        self.player.apply_action(PlayerState::TurningRight);
-                    }
-                }
-                Action::Airbrake => {
-                    if !AIRBRAKE_TOGGLE {
-                        self.player.airbrake = true;
-                    }
-                }
-                _ => {}
-            }
+        if self.control_handler.was_action_activated(&Action::Pause) {
+            self.control_handler.consume_presses();
+            // silence the seeker while paused, it picks back up on resume
+            self.lock_tone = self.lock_tone.transition(LockTone::Silent, self.assets.sounds(), self.settings.sfx_volume());
+            return Ok(GameStateAction::ChangeState(Box::new(super::pause::PauseGS::new(self.clone()))))
         }
 
-        // update the player
-        self.player.update(delta_time);
-
-        // handle the pause key with a key release to prevent spamming
-        let actions = self.control_handler.get_actions_up();
-        for action in actions {
-            match action {
-                Action::Pause => {
-                    return Ok(GameStateAction::ChangeState(Box::new(super::pause::PauseGS::new(self.clone()))))
-                }
-                Action::ThrottleUp => {
-                    if self.player.throttle_percent > 100.0 {
-                        self.player.throttle_percent = 100.0;
-                    }
-                }
-                Action::RollLeft => {
-                    if self.player.state != PlayerState::TurningRight {
-                        self.player.apply_action(PlayerState::Normal);
-                    }
-                }
-                Action::RollRight => {
-                    if self.player.state != PlayerState::TurningLeft {
-                        self.player.apply_action(PlayerState::Normal);
-                    }
-                }
-                Action::Airbrake => {
-                    if AIRBRAKE_TOGGLE {
-                        self.player.airbrake = !self.player.airbrake;
-                    } else {
-                        self.player.airbrake = false;
-                    }
-                }
-                _ => {}
+        // let the pilot hear what the seeker sees
+        let tone = LockTone::for_player(self.player());
+        self.lock_tone = self.lock_tone.transition(tone, self.assets.sounds(), self.settings.sfx_volume());
+
+        // the world wraps aircraft around the edges of the window, which can be resized
+        self.world.bounds = vec2(screen_width(), screen_height());
+
+        Ok(GameStateAction::NoOp)
+    }
+
+    fn update(&mut self, delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
+        // make the player respond to input
+        let cursor = (self.settings.steering == SteeringMode::MouseAim).then(|| render::cursor(self.world.bounds));
+        if let Some(player) = self.world.player_mut(self.player_id) {
+            player.input = self.control_handler.player_input(&player.input, player, cursor, delta_time.as_secs_f32());
+        }
+        self.control_handler.consume_presses();
+
+        // update the world
+        self.world.update(delta_time);
+        self.waves.update(&mut self.world, delta_time);
+
+        // the fight carries on for a moment after the player goes down, then it's over
+        if !self.player().is_alive() {
+            self.destroyed_for += delta_time.as_secs_f32();
+            if self.destroyed_for >= GAME_OVER_DELAY {
+                self.lock_tone = self.lock_tone.transition(LockTone::Silent, self.assets.sounds(), self.settings.sfx_volume());
+                let state = GameOverGS::new(self.assets.clone(), self.aircraft_id(), self.waves.wave_number());
+                return Ok(GameStateAction::ChangeState(Box::new(state)));
             }
         }
 
         Ok(GameStateAction::NoOp)
     }
 
-    fn draw(&self, fps: f32) -> Result<(), GameStateError> {
-        // clear the background and give a default color
-        clear_background(Color::from_rgba(11, 156, 209, 255));
-
-        // draw the player
-        draw_texture_ex(
-            &self.player.aircraft.sprite, 
-            self.player.pos.x, self.player.pos.y,  
-            WHITE,
-            DrawTextureParams {
-                rotation: self.player.rotation,
-                ..Default::default()
-            }
-        );
-
-        // draw the FPS counter in the top right
-        draw_text(&format!("FPS:      {}",      fps.round()),                        2.0, 12.0 * 1.0, 20.0, BLACK);
-        // -- blank space
-        let throttle_color = if self.player.throttle_percent > 100.0 {
-            Color::from_rgba(200, 50, 50, 255)
-        } else {
-            WHITE
-        };
-        draw_text(&format!("THROTTLE: {}%",     self.player.throttle_percent),       2.0, 12.0 * 3.0, 20.0, throttle_color);
-        draw_text(&format!("AIRBRAKE: {}",      self.player.airbrake),               2.0, 12.0 * 4.0, 20.0, WHITE);
-        draw_text(&format!("HEALTH:   {}",      self.player.health),                 2.0, 12.0 * 5.0, 20.0, WHITE);
-        let speed = (self.player.speed * 1.94384).round(); // convert m/s to knots
-        draw_text(&format!("SPEED:    {}kts",   speed),                              2.0, 12.0 * 6.0, 20.0, WHITE);
-        // -- blank space
-        // round the acceleration to 2 decimal places
-        let rounded_acc = (self.player.get_acceleration() * 100.0).round();
-        draw_text(&format!("ACCL:     {}m/s^2", rounded_acc),                        2.0, 12.0 * 8.0, 20.0, WHITE);
-        // round the turn rate to 2 decimal places
-        let rounded_turn_rate = (self.player.turn_rate * 100.0).round();
-        draw_text(&format!("T-RATE:   {}DEG/s", rounded_turn_rate),              2.0, 12.0 * 9.0, 20.0, WHITE);
-        let drag = (self.player.get_drag_coefficient() * 100.0).round();
-        draw_text(&format!("DRAG:     {}LBS",   drag), 2.0, 12.0 * 10.0, 20.0, WHITE);
-        let thrust = self.player.get_thrust().round();
-        draw_text(&format!("THRUST:   {}LBS",  thrust),                             2.0, 12.0 * 11.0, 20.0, WHITE);
+    fn draw(&self, fps: f32, interpolation: f32) -> Result<(), GameStateError> {
+        set_camera(&render::world_view(self.world.bounds));
+        render::draw_world(&self.assets, &self.world, interpolation)?;
+
+        let player = self.player();
+        render::draw_seeker(&self.world, player, interpolation);
+        if self.settings.steering == SteeringMode::MouseAim {
+            render::draw_mouse_aim(player, render::cursor(self.world.bounds), interpolation);
+        }
+        set_default_camera();
+
+        render::draw_hud(&self.world, player, Some(&self.waves.status()), fps, &self.settings);
 
         Ok(())
     }
 
-}
\ No newline at end of file
+}
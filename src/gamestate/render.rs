@@ -0,0 +1,282 @@
+use macroquad::{camera::Camera2D, color::{Color, BLACK, DARKGRAY, GREEN, ORANGE, RED, WHITE, YELLOW}, input::mouse_position, math::{vec2, Vec2}, shapes::{draw_circle, draw_circle_lines, draw_line, draw_rectangle, draw_rectangle_lines}, text::{draw_text, measure_text}, window::{clear_background, screen_height, screen_width}};
+
+use crate::{assets::{self, Assets, BULLET_SPRITE, MISSILE_SPRITE}, boss::{Boss, ComponentKind}, math, player::{Player, PIXELS_PER_METER}, pvp::MatchStatus, settings::Settings, waves::{WaveState, WaveStatus}, world::{Weapon, World}};
+
+use super::GameStateError;
+
+/// camera that fits a world of size `bounds` in the window, keeping its aspect ratio and centering it.
+/// the world is drawn with this set, the HUD is drawn on top with the default camera
+pub fn world_view(bounds: Vec2) -> Camera2D {
+    let screen = vec2(screen_width(), screen_height());
+    let scale = (screen.x / bounds.x).min(screen.y / bounds.y);
+    Camera2D {
+        target: bounds / 2.0,
+        // macroquad flips the y axis of cameras drawing to the screen, so this keeps y pointing down
+        zoom: 2.0 * scale / screen,
+        ..Default::default()
+    }
+}
+
+/// where the mouse is in a world of size `bounds`
+pub fn cursor(bounds: Vec2) -> Vec2 {
+    world_view(bounds).screen_to_world(mouse_position().into())
+}
+
+/// draw everything in the world, shared by local and networked games
+pub fn draw_world(assets: &Assets, world: &World, interpolation: f32) -> Result<(), GameStateError> {
+    // clear the background and give a default color
+    clear_background(Color::from_rgba(11, 156, 209, 255));
+
+    // draw the bullets
+    let bullet_sprite = assets.require_texture(BULLET_SPRITE)?;
+    for bullet in &world.bullets {
+        let pos = bullet.prev_pos.lerp(bullet.pos, interpolation);
+        assets::draw_centered(bullet_sprite, pos, bullet.rotation());
+    }
+
+    // draw the bosses, with their components on top so the player can see what to shoot at
+    for boss in &world.bosses {
+        let sprite = assets.require_texture(&boss.def.sprite)?;
+        let (pos, rotation) = boss.interpolated(interpolation);
+        assets::draw_scaled(sprite, pos, rotation, boss.def.scale);
+
+        for component in &boss.components {
+            let component_pos = boss.interpolated_component_pos(component, interpolation);
+            let color = if !component.is_alive() {
+                DARKGRAY
+            } else {
+                match component.def.kind {
+                    ComponentKind::Core => RED,
+                    ComponentKind::Turret => ORANGE,
+                    ComponentKind::Engine => YELLOW,
+                    ComponentKind::MissilePod => GREEN,
+                }
+            };
+            draw_circle(component_pos.x, component_pos.y, component.def.radius, Color::new(color.r, color.g, color.b, 0.35));
+            draw_circle_lines(component_pos.x, component_pos.y, component.def.radius, 2.0, color);
+        }
+    }
+
+    // draw the aircraft
+    for player in world.players.iter().filter(|p| p.is_alive()) {
+        let sprite = assets.require_texture(&player.aircraft.sprite)?;
+        let (pos, rotation) = player.interpolated(interpolation);
+        assets::draw_centered(sprite, pos, rotation);
+
+        // show that freshly spawned aircraft can't be hurt yet
+        if player.is_protected() {
+            draw_circle_lines(pos.x, pos.y, player.aircraft.hitbox_radius + 6.0, 2.0, Color::new(1.0, 1.0, 1.0, 0.6));
+        }
+    }
+
+    // draw the missiles
+    let missile_sprite = assets.require_texture(MISSILE_SPRITE)?;
+    for missile in &world.missiles {
+        let pos = missile.prev_pos.lerp(missile.pos, interpolation);
+        let rotation = missile.prev_rotation + (missile.rotation - missile.prev_rotation) * interpolation;
+        assets::draw_centered(missile_sprite, pos, rotation);
+    }
+
+    Ok(())
+}
+
+/// draw the seeker cone of `player` and whatever it is tracking
+pub fn draw_seeker(world: &World, player: &Player, interpolation: f32) {
+    if !player.is_alive() || player.launcher.missiles == 0 {
+        return;
+    }
+
+    let (pos, rotation) = player.interpolated(interpolation);
+    let stats = &player.aircraft.missile;
+    let range = stats.seeker_range * PIXELS_PER_METER;
+    let cone_color = Color::new(1.0, 1.0, 1.0, 0.15);
+    for side in [-1.0, 1.0] {
+        let edge = pos + math::direction(rotation + stats.seeker_cone * side) * range;
+        draw_line(pos.x, pos.y, edge.x, edge.y, 1.0, cone_color);
+    }
+
+    let tracked = player.seeker.candidate.and_then(|id| {
+        world.player(id).map(|p| (p.interpolated(interpolation).0, p.aircraft.hitbox_radius * 2.0))
+            .or_else(|| world.boss(id).map(|b| (b.interpolated(interpolation).0, b.radius() * 2.0)))
+    });
+    if let Some((target_pos, size)) = tracked {
+        let color = if player.seeker.locked.is_some() { RED } else { YELLOW };
+        draw_rectangle_lines(target_pos.x - size / 2.0, target_pos.y - size / 2.0, size, size, 2.0, color);
+    }
+}
+
+/// the mouse aim reticle, a circle on the cursor and a cross where the nose is actually pointing
+pub fn draw_mouse_aim(player: &Player, cursor: Vec2, interpolation: f32) {
+    if !player.is_alive() {
+        return;
+    }
+
+    draw_circle_lines(cursor.x, cursor.y, 10.0, 2.0, WHITE);
+
+    // put the nose marker as far out as the cursor so the two line up once the turn is done
+    let (pos, rotation) = player.interpolated(interpolation);
+    let nose = pos + math::direction(rotation) * pos.distance(cursor).max(player.aircraft.hitbox_radius * 2.0);
+    let size = 8.0;
+    draw_line(nose.x - size, nose.y, nose.x + size, nose.y, 2.0, YELLOW);
+    draw_line(nose.x, nose.y - size, nose.x, nose.y + size, 2.0, YELLOW);
+}
+
+/// draw the flight data, weapons and wave readouts for `player`, sized and filtered by `settings`
+pub fn draw_hud(world: &World, player: &Player, waves: Option<&WaveStatus>, fps: f32, settings: &Settings) {
+    let scale = settings.hud_scale;
+    let line = |text: String, row: f32, color: Color| {
+        draw_text(text, 2.0 * scale, 12.0 * scale * row, 20.0 * scale, color);
+    };
+
+    // draw the FPS counter in the top right
+    if settings.show_fps {
+        line(format!("FPS:      {}",      fps.round()),                        1.0, BLACK);
+    }
+    // -- blank space
+    let throttle_color = if player.throttle_percent > 100.0 {
+        Color::from_rgba(200, 50, 50, 255)
+    } else {
+        WHITE
+    };
+    line(format!("THROTTLE: {}%",     player.throttle_percent),                 3.0, throttle_color);
+    line(format!("AIRBRAKE: {}",      player.airbrake),                         4.0, WHITE);
+    line(format!("HEALTH:   {}",      player.health),                           5.0, WHITE);
+    let speed = settings.units.convert(player.speed).round();
+    line(format!("SPEED:    {}{}",    speed, settings.units.suffix()),          6.0, WHITE);
+    // -- blank space
+    // the rows below move up into the gap when the flight data is hidden
+    let mut row = 8.0;
+    if settings.show_flight_data {
+        // round the acceleration to 2 decimal places
+        let rounded_acc = (player.get_acceleration() * 100.0).round() / 100.0;
+        line(format!("ACCL:     {}m/s^2", rounded_acc),                         8.0, WHITE);
+        let rounded_turn_rate = player.turn_rate.to_degrees().round();
+        line(format!("T-RATE:   {}DEG/s", rounded_turn_rate),                   9.0, WHITE);
+        let drag = (player.get_drag() / 1000.0).round();
+        line(format!("DRAG:     {}kN",    drag),                                10.0, WHITE);
+        let thrust = (player.get_thrust() / 1000.0).round();
+        line(format!("THRUST:   {}kN",    thrust),                              11.0, WHITE);
+        // -- blank space
+        row = 13.0;
+    }
+    line(format!("AMMO:     {}",      player.gun.ammo),                         row, WHITE);
+    line(format!("MISSILES: {}",      player.launcher.missiles),                row + 1.0, WHITE);
+    let (seeker, seeker_color) = match (player.seeker.locked, player.seeker.candidate) {
+        (Some(_), _) => ("LOCK", RED),
+        (None, Some(_)) => ("TRACK", YELLOW),
+        _ => ("--", WHITE),
+    };
+    line(format!("SEEKER:   {}",      seeker),                                  row + 2.0, seeker_color);
+    // -- blank space
+    if let Some(waves) = waves {
+        line(format!("WAVE:     {}",      waves.number),                        row + 4.0, WHITE);
+        match waves.state {
+            WaveState::Breather(remaining) => {
+                line(format!("NEXT IN:  {}s", remaining.ceil()),                row + 5.0, WHITE);
+            }
+            _ => {
+                line(format!("DRONES:   {}",  waves.remaining),                 row + 5.0, WHITE);
+            }
+        }
+    }
+
+    if let Some(boss) = world.bosses.iter().find(|b| b.is_alive()) {
+        draw_boss_bar(boss);
+    }
+
+    if !player.is_alive() {
+        draw_banner("DESTROYED", RED);
+    }
+}
+
+/// the match clock, kill feed and respawn countdown of a PvP match
+pub fn draw_match_hud(status: &MatchStatus, player: &Player) {
+    // match clock and score limit across the top
+    let mut header = match status.time_left {
+        Some(time_left) => {
+            let seconds = time_left.ceil() as u32;
+            format!("{}:{:02}", seconds / 60, seconds % 60)
+        }
+        None => String::new(),
+    };
+    if status.score_limit > 0 {
+        if !header.is_empty() {
+            header.push_str(" - ");
+        }
+        header.push_str(&format!("FIRST TO {}", status.score_limit));
+    }
+    let size = measure_text(&header, None, 24, 1.0);
+    draw_text(&header, (screen_width() - size.width) / 2.0, 24.0, 24.0, WHITE);
+
+    // newest kills at the top right
+    for (i, kill) in status.kill_feed.iter().enumerate() {
+        let weapon = match kill.weapon {
+            Weapon::Gun => "GUN",
+            Weapon::Missile => "MSL",
+        };
+        let text = format!("{} [{}] {}", status.name(kill.killer), weapon, status.name(kill.victim));
+        let size = measure_text(&text, None, 20, 1.0);
+        let involved = kill.killer == player.id || kill.victim == player.id;
+        let color = if involved { YELLOW } else { WHITE };
+        draw_text(&text, screen_width() - size.width - 10.0, 24.0 + 20.0 * i as f32, 20.0, color);
+    }
+
+    if let Some(respawn_in) = status.score(player.id).and_then(|s| s.respawn_in) {
+        let text = format!("RESPAWN IN {}s", respawn_in.ceil());
+        let size = measure_text(&text, None, 30, 1.0);
+        draw_text(&text, (screen_width() - size.width) / 2.0, screen_height() / 2.0 + 40.0, 30.0, WHITE);
+    }
+}
+
+/// table of every player's kills and deaths, best first, with `player_id` highlighted
+pub fn draw_scoreboard(status: &MatchStatus, player_id: u32, title: &str) {
+    let row_height = 26.0;
+    let width = 420.0;
+    let height = 80.0 + row_height * status.scores.len() as f32;
+    let x = (screen_width() - width) / 2.0;
+    let y = (screen_height() - height) / 2.0;
+
+    draw_rectangle(x, y, width, height, Color::new(0.0, 0.0, 0.0, 0.6));
+    let size = measure_text(title, None, 30, 1.0);
+    draw_text(title, (screen_width() - size.width) / 2.0, y + 32.0, 30.0, WHITE);
+
+    let header_y = y + 60.0;
+    draw_text("PILOT", x + 20.0, header_y, 20.0, WHITE);
+    draw_text("KILLS", x + width - 160.0, header_y, 20.0, WHITE);
+    draw_text("DEATHS", x + width - 80.0, header_y, 20.0, WHITE);
+
+    for (i, score) in status.scores.iter().enumerate() {
+        let row_y = header_y + row_height * (i + 1) as f32;
+        let color = if score.id == player_id { YELLOW } else { WHITE };
+        draw_text(format!("{}. {}", i + 1, score.name), x + 20.0, row_y, 20.0, color);
+        draw_text(score.kills.to_string(), x + width - 160.0, row_y, 20.0, color);
+        draw_text(score.deaths.to_string(), x + width - 80.0, row_y, 20.0, color);
+    }
+}
+
+/// large text across the middle of the screen
+pub fn draw_banner(text: &str, color: Color) {
+    let size = measure_text(text, None, 60, 1.0);
+    draw_text(text, (screen_width() - size.width) / 2.0, screen_height() / 2.0, 60.0, color);
+}
+
+/// health bar across the top of the screen for the boss currently in the fight
+fn draw_boss_bar(boss: &Boss) {
+    let width = screen_width() * 0.5;
+    let height = 14.0;
+    let x = (screen_width() - width) / 2.0;
+    let y = 30.0;
+
+    let label = format!("{} - PHASE {}", boss.def.name.to_uppercase(), boss.phase + 1);
+    let size = measure_text(&label, None, 20, 1.0);
+    draw_text(&label, (screen_width() - size.width) / 2.0, y - 6.0, 20.0, WHITE);
+
+    draw_rectangle(x, y, width, height, Color::new(0.0, 0.0, 0.0, 0.5));
+    draw_rectangle(x, y, width * boss.health_fraction(), height, RED);
+    // mark where each later phase kicks in
+    for phase in boss.def.phases.iter().skip(1) {
+        let marker = x + width * phase.threshold;
+        draw_line(marker, y, marker, y + height, 2.0, WHITE);
+    }
+    draw_rectangle_lines(x, y, width, height, 2.0, WHITE);
+}
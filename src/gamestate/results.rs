@@ -0,0 +1,114 @@
+use std::{rc::Rc, time::Duration};
+
+use macroquad::{color::WHITE, math::vec2, text::{draw_text, measure_text}, window::{screen_height, screen_width}};
+
+use crate::{assets::Assets, controls::{Action, ControlHandler}, net::{client::Session, protocol::ServerPacket}, player::PlayerInput, pvp::MatchStatus};
+
+use super::{disconnected::DisconnectedGS, menu::{self, Menu, MenuEvent}, multiplayer::MultiplayerGS, render, GameState, GameStateAction, GameStateError};
+
+/// The final scores of a PvP match, shown until the server starts the next one
+pub struct ResultsGS {
+    assets: Rc<Assets>,
+    session: Option<Session>,
+    results: MatchStatus,
+    // seconds until the next match, counted down between snapshots
+    next_match_in: f32,
+    menu: Menu,
+    control_handler: ControlHandler,
+}
+
+impl ResultsGS {
+    pub fn new(assets: Rc<Assets>, session: Session, results: MatchStatus) -> Self {
+        Self {
+            assets,
+            session: Some(session),
+            next_match_in: results.next_match_in.unwrap_or_default(),
+            results,
+            // below the scoreboard
+            menu: Menu::new(&["LEAVE GAME"]).at(vec2((screen_width() - menu::ITEM_WIDTH) / 2.0, screen_height() - 110.0)),
+            control_handler: ControlHandler::load(),
+        }
+    }
+
+    fn disconnected(&mut self, reason: String) -> GameStateAction {
+        let session = self.session.take().expect("session is only taken when leaving");
+        GameStateAction::ChangeState(Box::new(DisconnectedGS::new(self.assets.clone(), &session.client, &session.aircraft_id, reason)))
+    }
+}
+
+impl GameState for ResultsGS {
+
+    fn handle_input(&mut self) -> Result<GameStateAction, GameStateError> {
+        self.control_handler.update();
+        let pause = self.control_handler.was_action_activated(&Action::Pause);
+        self.control_handler.consume_presses();
+
+        match self.menu.handle_input() {
+            Some(MenuEvent::Activated(_)) | Some(MenuEvent::Back) => Ok(self.disconnected("You left the game".to_string())),
+            _ if pause => Ok(self.disconnected("You left the game".to_string())),
+            _ => Ok(GameStateAction::NoOp),
+        }
+    }
+
+    fn update(&mut self, delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
+        let Some(session) = self.session.as_mut() else {
+            return Ok(GameStateAction::NoOp);
+        };
+
+        self.next_match_in = (self.next_match_in - delta_time.as_secs_f32()).max(0.0);
+
+        let mut next_match_started = false;
+        for packet in session.client.poll() {
+            match packet {
+                ServerPacket::Snapshot(snapshot) => {
+                    let Some(status) = snapshot.pvp else {
+                        continue;
+                    };
+                    if status.number > self.results.number {
+                        next_match_started = true;
+                    } else if let Some(next_match_in) = status.next_match_in {
+                        self.next_match_in = next_match_in;
+                    }
+                }
+                ServerPacket::Disconnect { reason } => {
+                    return Ok(self.disconnected(reason));
+                }
+                _ => {}
+            }
+        }
+
+        if session.client.timed_out() {
+            return Ok(self.disconnected("Connection timed out".to_string()));
+        }
+
+        // nothing to fly while the results are up, but the server needs to know we are still here
+        session.send_input(PlayerInput::default());
+
+        if next_match_started {
+            let session = self.session.take().expect("checked above");
+            return Ok(GameStateAction::ChangeState(Box::new(MultiplayerGS::new(self.assets.clone(), session))));
+        }
+
+        Ok(GameStateAction::NoOp)
+    }
+
+    fn draw(&self, _fps: f32, _interpolation: f32) -> Result<(), GameStateError> {
+        menu::draw_background();
+
+        let player_id = self.session.as_ref().map(|s| s.player_id).unwrap_or_default();
+        let title = match self.results.scores.first() {
+            Some(winner) if winner.id == player_id => "VICTORY".to_string(),
+            Some(winner) => format!("{} WINS", winner.name.to_uppercase()),
+            None => "MATCH OVER".to_string(),
+        };
+        render::draw_scoreboard(&self.results, player_id, &title);
+
+        let hint = format!("NEXT MATCH IN {}s", self.next_match_in.ceil());
+        let size = measure_text(&hint, None, 20, 1.0);
+        draw_text(&hint, (screen_width() - size.width) / 2.0, screen_height() - 120.0, 20.0, WHITE);
+        self.menu.draw("");
+
+        Ok(())
+    }
+
+}
@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use macroquad::{color::WHITE, input::{is_key_pressed, KeyCode}, text::{draw_text, measure_text}, window::{screen_height, screen_width}};
+
+use crate::{gamepad::{self, Button}, settings::{Settings, FPS_CAPS, HUD_SCALE_MAX, HUD_SCALE_MIN, RESOLUTIONS}, warn};
+
+use super::{menu::{self, Menu, MenuEvent}, GameState, GameStateAction, GameStateError};
+
+const RESOLUTION: usize = 0;
+const FULLSCREEN: usize = 1;
+const VSYNC: usize = 2;
+const FPS_CAP: usize = 3;
+const MASTER_VOLUME: usize = 4;
+const SFX_VOLUME: usize = 5;
+const HUD_SCALE: usize = 6;
+const UNITS: usize = 7;
+const STEERING: usize = 8;
+const SHOW_FPS: usize = 9;
+const SHOW_FLIGHT_DATA: usize = 10;
+const DEBUG_OUTPUT: usize = 11;
+const LOG_TO_FILE: usize = 12;
+const RESET: usize = 13;
+const BACK: usize = 14;
+
+// how much one press of left or right moves a volume or the HUD scale
+const STEP: f32 = 0.1;
+
+/// Video, audio and gameplay settings, each changed with left and right and saved straight away
+pub struct SettingsGS {
+    settings: Settings,
+    menu: Menu,
+    // the screen to go back to, whichever one opened the settings
+    previous: Option<Box<dyn GameState>>,
+    // why the last change couldn't be saved, cleared by the next one that is
+    save_error: Option<String>,
+}
+
+impl SettingsGS {
+    pub fn new(previous: Box<dyn GameState>) -> Self {
+        let labels = [""; LOG_TO_FILE + 1].into_iter()
+            .chain(["RESET TO DEFAULTS", "BACK"])
+            .collect::<Vec<&str>>();
+
+        let mut state = Self {
+            settings: Settings::load(),
+            menu: Menu::new(&labels),
+            previous: Some(previous),
+            save_error: None,
+        };
+        state.refresh_labels();
+        state
+    }
+
+    fn refresh_labels(&mut self) {
+        let s = &self.settings;
+        let on_off = |on: bool| if on { "ON" } else { "OFF" };
+        let percent = |v: f32| format!("{}%", (v * 100.0).round());
+        let fps_cap = if s.fps_cap == 0 { "UNCAPPED".to_string() } else { s.fps_cap.to_string() };
+
+        let labels = [
+            (RESOLUTION, format!("RESOLUTION: {}x{}", s.width, s.height)),
+            (FULLSCREEN, format!("FULLSCREEN: {}", on_off(s.fullscreen))),
+            (VSYNC, format!("VSYNC: {}", on_off(s.vsync))),
+            (FPS_CAP, format!("FPS CAP: {}", fps_cap)),
+            (MASTER_VOLUME, format!("MASTER VOLUME: {}", percent(s.master_volume))),
+            (SFX_VOLUME, format!("SFX VOLUME: {}", percent(s.sfx_volume))),
+            (HUD_SCALE, format!("HUD SCALE: {}", percent(s.hud_scale))),
+            (UNITS, format!("SPEED UNITS: {}", s.units.to_string().to_uppercase())),
+            (STEERING, format!("STEERING: {}", s.steering.to_string().to_uppercase())),
+            (SHOW_FPS, format!("SHOW FPS: {}", on_off(s.show_fps))),
+            (SHOW_FLIGHT_DATA, format!("FLIGHT DATA: {}", on_off(s.show_flight_data))),
+            (DEBUG_OUTPUT, format!("DEBUG OUTPUT: {}", on_off(s.debug_output))),
+            (LOG_TO_FILE, format!("LOG FILE: {}", on_off(s.log_to_file))),
+        ];
+        for (i, label) in labels {
+            self.menu.set_label(i, label);
+        }
+    }
+
+    /// move the setting at `item` one step in `direction`, then put it into effect and save it
+    fn adjust(&mut self, item: usize, direction: isize) {
+        let s = &mut self.settings;
+        match item {
+            RESOLUTION => {
+                let current = RESOLUTIONS.iter().position(|r| *r == (s.width, s.height));
+                (s.width, s.height) = RESOLUTIONS[cycle(current, direction, RESOLUTIONS.len())];
+            }
+            FULLSCREEN => s.fullscreen = !s.fullscreen,
+            VSYNC => s.vsync = !s.vsync,
+            FPS_CAP => {
+                let current = FPS_CAPS.iter().position(|cap| *cap == s.fps_cap);
+                s.fps_cap = FPS_CAPS[cycle(current, direction, FPS_CAPS.len())];
+            }
+            MASTER_VOLUME => s.master_volume = step(s.master_volume, direction, 0.0, 1.0),
+            SFX_VOLUME => s.sfx_volume = step(s.sfx_volume, direction, 0.0, 1.0),
+            HUD_SCALE => s.hud_scale = step(s.hud_scale, direction, HUD_SCALE_MIN, HUD_SCALE_MAX),
+            UNITS => s.units = s.units.next(),
+            STEERING => s.steering = s.steering.next(),
+            SHOW_FPS => s.show_fps = !s.show_fps,
+            SHOW_FLIGHT_DATA => s.show_flight_data = !s.show_flight_data,
+            DEBUG_OUTPUT => s.debug_output = !s.debug_output,
+            LOG_TO_FILE => s.log_to_file = !s.log_to_file,
+            _ => return,
+        }
+
+        // only touch the window when it's what changed, so a manual resize isn't undone by changing the volume
+        if matches!(item, RESOLUTION | FULLSCREEN) {
+            self.settings.apply();
+        } else {
+            self.settings.apply_globals();
+        }
+        self.save();
+    }
+
+    /// the change is in effect either way, but the player should know if it won't last past this session
+    fn save(&mut self) {
+        self.save_error = self.settings.save().err().map(|e| {
+            warn!("{}", e);
+            e.to_string()
+        });
+        self.refresh_labels();
+    }
+
+    fn back(&mut self) -> GameStateAction {
+        match self.previous.take() {
+            Some(previous) => GameStateAction::ChangeState(previous),
+            None => GameStateAction::NoOp,
+        }
+    }
+}
+
+/// the index `direction` away from `current` in a list of `len`, wrapping at the ends
+fn cycle(current: Option<usize>, direction: isize, len: usize) -> usize {
+    match current {
+        Some(i) => (i as isize + direction).rem_euclid(len as isize) as usize,
+        // a value that isn't in the list (i.e. edited by hand) starts over from the first
+        None => 0,
+    }
+}
+
+// rounded to the step so repeated presses don't drift
+fn step(value: f32, direction: isize, min: f32, max: f32) -> f32 {
+    ((value / STEP).round() * STEP + STEP * direction as f32).clamp(min, max)
+}
+
+impl GameState for SettingsGS {
+
+    fn handle_input(&mut self) -> Result<GameStateAction, GameStateError> {
+        let selected = self.menu.selected();
+        if is_key_pressed(KeyCode::Left) || gamepad::is_button_pressed(Button::DPadLeft) {
+            self.adjust(selected, -1);
+        }
+        if is_key_pressed(KeyCode::Right) || gamepad::is_button_pressed(Button::DPadRight) {
+            self.adjust(selected, 1);
+        }
+
+        match self.menu.handle_input() {
+            Some(MenuEvent::Activated(RESET)) => {
+                self.settings = Settings::default();
+                self.settings.apply();
+                self.save();
+                Ok(GameStateAction::NoOp)
+            }
+            Some(MenuEvent::Activated(BACK)) | Some(MenuEvent::Back) => Ok(self.back()),
+            Some(MenuEvent::Activated(i)) => {
+                self.adjust(i, 1);
+                Ok(GameStateAction::NoOp)
+            }
+            None => Ok(GameStateAction::NoOp),
+        }
+    }
+
+    fn update(&mut self, _delta_time: &Duration) -> Result<GameStateAction, GameStateError> {
+        Ok(GameStateAction::NoOp)
+    }
+
+    fn draw(&self, _fps: f32, _interpolation: f32) -> Result<(), GameStateError> {
+        menu::draw_background();
+        self.menu.draw("SETTINGS");
+
+        // the swap interval can only be set when the window is created
+        let hint = if self.menu.selected() == VSYNC {
+            "VSYNC CHANGES TAKE EFFECT AFTER A RESTART"
+        } else {
+            "LEFT/RIGHT: CHANGE    ESC: BACK"
+        };
+        let size = measure_text(hint, None, 20, 1.0);
+        draw_text(hint, (screen_width() - size.width) / 2.0, screen_height() - 30.0, 20.0, WHITE);
+
+        if let Some(e) = &self.save_error {
+            menu::draw_notice(&format!("SETTINGS NOT SAVED, CHANGES WILL BE LOST ON EXIT ({})", e));
+        }
+
+        Ok(())
+    }
+
+}
@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+pub mod logging;
+pub mod gamestate;
+pub mod config;
+pub mod controls;
+pub mod player;
+pub mod aircraft;
+pub mod ai;
+pub mod assets;
+pub mod audio;
+pub mod boss;
+pub mod collision;
+pub mod gamepad;
+pub mod math;
+pub mod net;
+pub mod pvp;
+pub mod settings;
+pub mod storage;
+pub mod timestep;
+pub mod weapons;
+pub mod waves;
+pub mod world;
+
+// the simulation always steps at this rate regardless of the framerate
+pub const TICK_RATE: u64 = 60;
+pub const SIMULATION_TIMESTEP: Duration = Duration::from_nanos(1_000_000_000 / TICK_RATE);
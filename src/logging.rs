@@ -1,83 +1,308 @@
-use std::{fmt, time::Duration};
+use std::{collections::VecDeque, fmt::{self, Display, Formatter}, fs::{self, File}, io::Write, panic, path::{Path, PathBuf}, str::FromStr, sync::{atomic::{AtomicBool, AtomicU64, Ordering}, Mutex, RwLock}, time::{Duration, SystemTime, UNIX_EPOCH}};
 
 use better_term::{flush_styles, Color};
 
-use crate::DEBUG_OUTPUT;
+use crate::storage;
 
-pub(crate) fn timed<F: FnOnce() -> R, R>(f: F) -> (R, Duration) {
+// debug builds are for working on the game, so they say more
+pub const DEFAULT_LEVEL: LogLevel = if cfg!(debug_assertions) { LogLevel::Debug } else { LogLevel::Info };
+const LOG_FILE: &str = "merlin.log";
+// older logs are kept as merlin.1.log, merlin.2.log, ... up to this many
+const KEPT_LOGS: usize = 5;
+// a log bigger than this is rotated while the game is still running
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+// lines kept in memory to go in the crash log
+const RECENT_LINES: usize = 200;
+
+static FILTER: RwLock<LogFilter> = RwLock::new(LogFilter::new(DEFAULT_LEVEL));
+// set once the filter comes from the command line, so the settings don't change it back
+static FILTER_OVERRIDDEN: AtomicBool = AtomicBool::new(false);
+// 0 until the game loop starts counting
+static FRAME: AtomicU64 = AtomicU64::new(0);
+static SINK: Mutex<Option<FileSink>> = Mutex::new(None);
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+pub fn timed<F: FnOnce() -> R, R>(f: F) -> (R, Duration) {
     let start = std::time::Instant::now();
     let result = f();
     let elapsed = start.elapsed();
     (result, elapsed)
 }
 
-pub(crate) fn _debug(args: fmt::Arguments) {
-    if !DEBUG_OUTPUT {
+/// How important a log message is, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warn" | "warning" => Ok(LogLevel::Warn),
+            "error" => Ok(LogLevel::Error),
+            _ => Err(format!("Unknown log level '{}', expected debug, info, warn or error", s)),
+        }
+    }
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LogLevel::Debug => write!(f, "DBG"),
+            LogLevel::Info => write!(f, "INF"),
+            LogLevel::Warn => write!(f, "WRN"),
+            LogLevel::Error => write!(f, "ERR"),
+        }
+    }
+}
+
+/// Which messages get logged, a level for everything with overrides for single modules
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    default: LogLevel,
+    // module path and the level for it and everything inside it
+    modules: Vec<(String, LogLevel)>,
+}
+
+impl LogFilter {
+    pub const fn new(default: LogLevel) -> Self {
+        Self {
+            default,
+            modules: Vec::new(),
+        }
+    }
+
+    /// the quietest level still logged from `module`, taken from the most specific module that matches
+    pub fn level_for(&self, module: &str) -> LogLevel {
+        self.modules.iter()
+            .filter(|(path, _)| module.strip_prefix(path.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with("::")))
+            .max_by_key(|(path, _)| path.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+}
+
+/// `info`, or `info,merlin::net=debug,merlin::ai=warn` for different levels in different modules
+impl FromStr for LogFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = LogFilter::new(DEFAULT_LEVEL);
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            match part.split_once('=') {
+                Some((module, level)) => filter.modules.push((module.trim().to_string(), level.parse()?)),
+                None => filter.default = part.parse()?,
+            }
+        }
+        Ok(filter)
+    }
+}
+
+/// the level chosen in the settings, ignored if a filter was given on the command line
+pub fn set_level(level: LogLevel) {
+    if !FILTER_OVERRIDDEN.load(Ordering::Relaxed) {
+        FILTER.write().unwrap_or_else(|e| e.into_inner()).default = level;
+    }
+}
+
+/// the filter given on the command line, which wins over the settings for the rest of the run
+pub fn override_filter(filter: LogFilter) {
+    FILTER_OVERRIDDEN.store(true, Ordering::Relaxed);
+    *FILTER.write().unwrap_or_else(|e| e.into_inner()) = filter;
+}
+
+/// called by the game loop at the start of every frame so log lines can be matched up with what was on screen
+pub fn set_frame(frame: u64) {
+    FRAME.store(frame, Ordering::Relaxed);
+}
+
+/// Writes every log line to a file as well, moving old logs aside so the last few runs are kept
+struct FileSink {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl FileSink {
+    fn open(dir: &Path) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        rotate(dir)?;
+        let mut file = File::create(dir.join(LOG_FILE))?;
+        let header = format!("merlin {} log started {} UTC\n", env!("CARGO_PKG_VERSION"), date_time(SystemTime::now()));
+        file.write_all(header.as_bytes())?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file,
+            size: header.len() as u64,
+        })
+    }
+
+    fn write(&mut self, line: &str) -> std::io::Result<()> {
+        if self.size + line.len() as u64 > MAX_LOG_SIZE {
+            *self = Self::open(&self.dir)?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.file.write_all(b"\n")?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+// merlin.log -> merlin.1.log -> merlin.2.log ..., dropping the oldest
+fn rotate(dir: &Path) -> std::io::Result<()> {
+    let numbered = |i: usize| dir.join(format!("merlin.{}.log", i));
+    let _ = fs::remove_file(numbered(KEPT_LOGS));
+    for i in (1..KEPT_LOGS).rev() {
+        if numbered(i).exists() {
+            fs::rename(numbered(i), numbered(i + 1))?;
+        }
+    }
+    let current = dir.join(LOG_FILE);
+    if current.exists() {
+        fs::rename(current, numbered(1))?;
+    }
+    Ok(())
+}
+
+/// start writing the log to a file in `dir`, or stop if it's None
+pub fn set_file(dir: Option<&Path>) -> Result<(), String> {
+    let mut sink = SINK.lock().unwrap_or_else(|e| e.into_inner());
+    if sink.as_ref().map(|s| s.dir.as_path()) == dir {
+        return Ok(());
+    }
+    *sink = None;
+    if let Some(dir) = dir {
+        let opened = FileSink::open(dir).map_err(|e| format!("Failed to open a log file in {}: {}", dir.display(), e))?;
+        *sink = Some(opened);
+    }
+    Ok(())
+}
+
+#[doc(hidden)]
+pub fn _log(level: LogLevel, module: &str, args: fmt::Arguments) {
+    if level < FILTER.read().unwrap_or_else(|e| e.into_inner()).level_for(module) {
         return;
     }
-    println!(
-        "{}# DBG > {}{}",
-        Color::BrightBlack,
-        Color::White,
-        args,
-    );
+
+    let time = time_of_day(SystemTime::now());
+    let frame = match FRAME.load(Ordering::Relaxed) {
+        0 => String::new(),
+        frame => format!(" #{}", frame),
+    };
+    let (symbol, tag_color, text_color) = match level {
+        LogLevel::Debug => ('#', Color::BrightBlack, Color::White),
+        LogLevel::Info => (':', Color::Cyan, Color::BrightWhite),
+        LogLevel::Warn => ('!', Color::Yellow, Color::BrightYellow),
+        LogLevel::Error => ('X', Color::Red, Color::BrightRed),
+    };
+    println!("{}{}{} {} {} > {}{}", tag_color, time, frame, symbol, level, text_color, args);
     flush_styles();
+
+    // the file and the crash log get the module too, since there's no color to tell lines apart
+    let line = format!("{}{} {} {} > {}", time, frame, level, module, args);
+    {
+        let mut sink = SINK.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(file) = sink.as_mut() {
+            if let Err(e) = file.write(&line) {
+                // logging about the log would just end up back here
+                eprintln!("Failed to write to the log file, no longer writing to it: {}", e);
+                *sink = None;
+            }
+        }
+    }
+    let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    if recent.len() == RECENT_LINES {
+        recent.pop_front();
+    }
+    recent.push_back(line);
+}
+
+/// write a crash log with the panic and the last few log lines before handing the panic to the default hook
+pub fn install_panic_hook() {
+    let default = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let mut report = format!(
+            "merlin {} crashed {} UTC on frame {}\n\n{}\n\n{}\n\nrecent log lines:\n",
+            env!("CARGO_PKG_VERSION"),
+            date_time(SystemTime::now()),
+            FRAME.load(Ordering::Relaxed),
+            info,
+            std::backtrace::Backtrace::force_capture(),
+        );
+        // the panic may have happened while logging, don't wait on a lock that will never be released
+        if let Ok(recent) = RECENT.try_lock() {
+            for line in recent.iter() {
+                report.push_str(line);
+                report.push('\n');
+            }
+        }
+
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let path = storage::log_dir().join(format!("crash-{}.log", seconds));
+        let written = fs::create_dir_all(storage::log_dir()).and_then(|_| fs::write(&path, report));
+        match written {
+            Ok(()) => eprintln!("Crash log written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write crash log to {}: {}", path.display(), e),
+        }
+
+        default(info);
+    }));
+}
+
+// HH:MM:SS.mmm in UTC
+fn time_of_day(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs() % 86400;
+    format!("{:02}:{:02}:{:02}.{:03}", seconds / 3600, seconds / 60 % 60, seconds % 60, since_epoch.subsec_millis())
+}
+
+// YYYY-MM-DD HH:MM:SS.mmm in UTC
+fn date_time(time: SystemTime) -> String {
+    let days = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() / 86400;
+    // days since 1970 to a calendar date, from Howard Hinnant's civil_from_days
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{}-{:02}-{:02} {}", year, month, day, time_of_day(time))
 }
 
 #[macro_export]
 macro_rules! debug {
     ($($arg:tt)*) => {
-        $crate::logging::_debug(format_args!($($arg)*));
+        $crate::logging::_log($crate::logging::LogLevel::Debug, module_path!(), format_args!($($arg)*));
     };
 }
 
-pub(crate) fn _info(args: fmt::Arguments) {
-    println!(
-        "{}: INF > {}{}",
-        Color::Cyan,
-        Color::BrightWhite,
-        args,
-    );
-    flush_styles();
-}
-
 #[macro_export]
 macro_rules! info {
     ($($arg:tt)*) => {
-        $crate::logging::_info(format_args!($($arg)*));
+        $crate::logging::_log($crate::logging::LogLevel::Info, module_path!(), format_args!($($arg)*));
     };
 }
 
-pub(crate) fn _warn(args: fmt::Arguments) {
-    println!(
-        "{}! WRN > {}{}",
-        Color::Yellow,
-        Color::BrightYellow,
-        args,
-    );
-    flush_styles();
-}
-
 #[macro_export]
 macro_rules! warn {
     ($($arg:tt)*) => {
-        $crate::logging::_warn(format_args!($($arg)*));
+        $crate::logging::_log($crate::logging::LogLevel::Warn, module_path!(), format_args!($($arg)*));
     };
 }
 
-pub(crate) fn _error(args: fmt::Arguments) {
-    println!(
-        "{}X ERR > {}{}",
-        Color::Red,
-        Color::BrightRed,
-        args,
-    );
-    flush_styles();
-}
-
 #[macro_export]
 macro_rules! error {
     ($($arg:tt)*) => {
-        $crate::logging::_error(format_args!($($arg)*));
+        $crate::logging::_log($crate::logging::LogLevel::Error, module_path!(), format_args!($($arg)*));
     };
-}
\ No newline at end of file
+}
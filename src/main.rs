@@ -1,37 +1,203 @@
-use std::time::Instant;
-use gamestate::GameState;
+use std::{net::SocketAddr, path::PathBuf, rc::Rc, sync::OnceLock, time::{Duration, Instant}};
 use macroquad::prelude::*;
+use merlin::{assets, debug, error, gamepad, gamestate::{self, GameState, GameStateAction}, info, logging::{self, LogFilter}, net, settings::{self, Settings}, storage, timestep::FixedTimestep, SIMULATION_TIMESTEP};
 
-pub mod logging;
-pub mod gamestate;
-pub mod controls;
-pub mod player;
-pub mod aircraft;
-
-pub const DEBUG_OUTPUT: bool = true;
 const FPS_SMOOTHING_FRAMES: usize = 30;
+const DEFAULT_AIRCRAFT: &str = "gripen";
+
+const USAGE: &str = "\
+Usage: merlin [options]
+
+Options:
+    --aircraft <id>            fly this aircraft, i.e. gripen
+    --mode <menu|survival|host>
+                               start in the main menu, go straight into a survival mission,
+                               or host a game on this machine and join it
+    --host                     same as --mode host
+    --connect <addr>           join someone else's game, i.e. 127.0.0.1:27015
+    --seed <n>                 seed the random number generator so a run can be repeated
+    --windowed, --fullscreen   override the window mode from the settings for this run
+    --log-level <filter>       debug, info, warn or error, with levels for single modules after it,
+                               i.e. info,merlin::net=debug (default from MERLIN_LOG)
+    --config-dir <dir>         keep settings and controls in this directory
+    --portable                 keep everything next to the executable
+    --help                     show this message";
+
+/// What the game should do once it has loaded
+#[derive(Debug, Clone, Copy)]
+enum LaunchMode {
+    Menu,
+    Survival,
+    // run a server in the background and join it
+    Host,
+    Connect(SocketAddr),
+}
+
+/// Everything given on the command line
+#[derive(Debug, Clone)]
+struct Options {
+    aircraft: Option<String>,
+    mode: LaunchMode,
+    seed: Option<u64>,
+    fullscreen: Option<bool>,
+    log_level: Option<LogFilter>,
+    storage: storage::StorageOptions,
+}
+
+// parsed before the window opens and read again once the game starts
+static OPTIONS: OnceLock<Options> = OnceLock::new();
+
+fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        return Ok(None);
+    }
+
+    let mut options = Options {
+        aircraft: None,
+        mode: LaunchMode::Menu,
+        seed: None,
+        fullscreen: None,
+        log_level: std::env::var("MERLIN_LOG").ok().map(|filter| filter.parse()).transpose()
+            .map_err(|e| format!("Invalid MERLIN_LOG: {}", e))?,
+        storage: storage::StorageOptions::from_env(),
+    };
+    let mut mode_set = false;
+    let mut set_mode = |options: &mut Options, mode| {
+        if std::mem::replace(&mut mode_set, true) {
+            return Err("Only one of --mode, --host and --connect can be used".to_string());
+        }
+        options.mode = mode;
+        Ok(())
+    };
+
+    // skip the executable
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--aircraft" => {
+                options.aircraft = Some(value()?.clone());
+            }
+            "--mode" => {
+                let mode = match value()?.to_lowercase().as_str() {
+                    "menu" => LaunchMode::Menu,
+                    "survival" => LaunchMode::Survival,
+                    "host" => LaunchMode::Host,
+                    mode => return Err(format!("Unknown mode '{}', expected menu, survival or host", mode)),
+                };
+                set_mode(&mut options, mode)?;
+            }
+            "--host" => {
+                set_mode(&mut options, LaunchMode::Host)?;
+            }
+            "--connect" => {
+                let addr = value()?.parse()
+                    .map_err(|e| format!("Invalid address for --connect, i.e. 127.0.0.1:{}: {}", net::DEFAULT_PORT, e))?;
+                set_mode(&mut options, LaunchMode::Connect(addr))?;
+            }
+            "--seed" => {
+                options.seed = Some(value()?.parse().map_err(|e| format!("Invalid number for --seed: {}", e))?);
+            }
+            "--windowed" => {
+                options.fullscreen = Some(false);
+            }
+            "--fullscreen" => {
+                options.fullscreen = Some(true);
+            }
+            "--log-level" => {
+                options.log_level = Some(value()?.parse()?);
+            }
+            "--config-dir" => {
+                options.storage.config_dir = Some(PathBuf::from(value()?));
+            }
+            "--portable" => {
+                options.storage.portable = true;
+            }
+            _ => return Err(format!("Unknown argument '{}'", arg)),
+        }
+    }
+
+    Ok(Some(options))
+}
 
+// runs before main, so the command line is read here for the window and the settings it is built from
 fn window_config() -> Conf {
-    Conf {
+    logging::install_panic_hook();
+
+    let args: Vec<String> = std::env::args().collect();
+    let options = match parse_args(&args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            std::process::exit(0);
+        }
+        Err(e) => {
+            error!("{}", e);
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Some(filter) = options.log_level.clone() {
+        logging::override_filter(filter);
+    }
+    storage::init(&options.storage);
+
+    let settings = Settings::load();
+    settings.apply_globals();
+
+    let mut conf = Conf {
         window_title: "Merlin".to_string(),
-        window_width: 1200,
-        window_height: 800,
+        window_width: settings.width as i32,
+        window_height: settings.height as i32,
+        fullscreen: options.fullscreen.unwrap_or(settings.fullscreen),
         ..Default::default()
-    }
+    };
+    conf.platform.swap_interval = Some(if settings.vsync { 1 } else { 0 });
+
+    let _ = OPTIONS.set(options);
+    conf
 }
 
 #[macroquad::main(window_config)]
 async fn main() {
 
+    let options = OPTIONS.get().cloned().expect("options are parsed before the window opens");
+    debug!("Config directory: {}", storage::config_dir().display());
+
+    if let Some(seed) = options.seed {
+        info!("Seeding the random number generator with {}", seed);
+        rand::srand(seed);
+    }
+
     debug!("Initializing assets...");
 
-    // todo: handle assets here?
+    let assets = match assets::Assets::load().await {
+        Ok(assets) => Rc::new(assets),
+        Err(e) => {
+            error!("Failed to load assets: {}", e);
+            return;
+        }
+    };
+
+    debug!("Assets initialized. {} aircraft available.", assets.aircraft().len());
+
+    let aircraft_id = options.aircraft.as_deref().unwrap_or(DEFAULT_AIRCRAFT);
+    if assets.get_aircraft(aircraft_id).is_none() {
+        let ids: Vec<&str> = assets.aircraft().iter().map(|a| a.id.as_str()).collect();
+        error!("Unknown aircraft '{}', expected one of {}", aircraft_id, ids.join(", "));
+        return;
+    }
 
-    debug!("Assets initialized.");
     debug!("Initializing gamestate...");
-    
+
     // create a dynamic gamestate object
-    let gamestate = gamestate::playing::PlayingGS::new().await;
+    let gamestate: Result<Box<dyn GameState>, _> = match options.mode {
+        LaunchMode::Menu => Ok(Box::new(gamestate::main_menu::MainMenuGS::new(assets.clone(), aircraft_id)) as Box<dyn GameState>),
+        LaunchMode::Survival => gamestate::playing::PlayingGS::new(assets.clone(), aircraft_id).map(|gs| gs as Box<dyn GameState>),
+        LaunchMode::Host => gamestate::connecting::ConnectingGS::host(assets.clone(), aircraft_id).map(|gs| gs as Box<dyn GameState>),
+        LaunchMode::Connect(addr) => gamestate::connecting::ConnectingGS::new(assets.clone(), addr, aircraft_id).map(|gs| gs as Box<dyn GameState>),
+    };
     if let Err(e) = gamestate {
         return eprintln!("Failed to initialize gamestate: {}", e);
     }
@@ -42,19 +208,23 @@ async fn main() {
     
     // handle FPS calculations
     let mut last_time = Instant::now();
-    let mut fps_values = vec![0.0; FPS_SMOOTHING_FRAMES];
+    let mut fps_values = [0.0; FPS_SMOOTHING_FRAMES];
     let mut fps_index = 0;
     let mut fps_sum = 0.0;
+    let mut timestep = FixedTimestep::new();
+    let mut frame: u64 = 0;
 
     'game_loop: loop { // -- game loop --
 
+        frame += 1;
+        logging::set_frame(frame);
+
         // Calculate delta time
         let now = Instant::now();
         let delta_time = now - last_time;
         last_time = now;
         // Convert delta time to seconds as a float
         let delta_seconds = delta_time.as_secs_f32();
-        // Use delta_seconds for movement, animation, etc.
 
         let fps = {
             // FPS calculations
@@ -72,30 +242,63 @@ async fn main() {
             fps_sum / FPS_SMOOTHING_FRAMES as f32
         };
 
-        // call the gamestate update function
-        let update_result = gamestate.update(&delta_time);
-        if let Err(update_error) = update_result {
-            error!("Error updating gamestate: {:?}", update_error);
+        // handle input once per frame
+        gamepad::update();
+        let input_result = gamestate.handle_input();
+        if let Err(input_error) = input_result {
+            error!("Error handling gamestate input: {:?}", input_error);
+            break 'game_loop;
+        }
+        if !apply_gamestate_action(&mut gamestate, input_result.unwrap()) {
             break 'game_loop;
         }
-        let gamestate_action = update_result.unwrap();
-        match gamestate_action {
-            gamestate::GameStateAction::NoOp => {},
-            gamestate::GameStateAction::ChangeState(new_state) => {
-                gamestate = new_state;
-            },
-            gamestate::GameStateAction::Exit => {
+
+        // step the simulation at a fixed rate
+        for _ in 0..timestep.advance(delta_time) {
+            // call the gamestate update function
+            let update_result = gamestate.update(&SIMULATION_TIMESTEP);
+            if let Err(update_error) = update_result {
+                error!("Error updating gamestate: {:?}", update_error);
+                break 'game_loop;
+            }
+            if !apply_gamestate_action(&mut gamestate, update_result.unwrap()) {
                 break 'game_loop;
             }
         }
 
+        // how far between simulation steps this frame is rendered
+        let interpolation = timestep.interpolation();
+
         // call the gamestate's draw function
-        if let Err(draw_error) = gamestate.draw(fps) {
+        if let Err(draw_error) = gamestate.draw(fps, interpolation) {
             error!("Error drawing gamestate: {:?}", draw_error);
             break 'game_loop;
         }
 
+        // hold the frame rate down to the cap by sleeping off whatever is left of this frame
+        let fps_cap = settings::fps_cap();
+        if fps_cap > 0 {
+            let frame_time = Duration::from_secs_f64(1.0 / fps_cap as f64);
+            if let Some(remaining) = frame_time.checked_sub(now.elapsed()) {
+                std::thread::sleep(remaining);
+            }
+        }
+
         // call the next frame
         next_frame().await;
     } // -- game loop --
 }
+
+/// apply a gamestate's requested action, returns false if the game should exit
+fn apply_gamestate_action(gamestate: &mut Box<dyn GameState>, action: GameStateAction) -> bool {
+    match action {
+        GameStateAction::NoOp => {},
+        GameStateAction::ChangeState(new_state) => {
+            *gamestate = new_state;
+        },
+        GameStateAction::Exit => {
+            return false;
+        }
+    }
+    true
+}
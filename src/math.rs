@@ -0,0 +1,21 @@
+use std::f32::consts::{PI, TAU};
+
+use macroquad::math::{vec2, Vec2};
+
+// rotations follow the sprites: 0 is pointing up the screen and positive turns clockwise
+
+/// unit vector for a rotation, in screen space
+pub fn direction(rotation: f32) -> Vec2 {
+    vec2(rotation.sin(), -rotation.cos())
+}
+
+/// the rotation that points from `from` towards `to`
+pub fn heading_to(from: Vec2, to: Vec2) -> f32 {
+    let offset = to - from;
+    offset.x.atan2(-offset.y)
+}
+
+/// the shortest signed turn from rotation `from` to rotation `to`, in the range -PI..PI
+pub fn angle_difference(from: f32, to: f32) -> f32 {
+    (to - from + PI).rem_euclid(TAU) - PI
+}
@@ -0,0 +1,148 @@
+use std::{io::ErrorKind, net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket}, time::Instant};
+
+use macroquad::math::Vec2;
+
+use crate::{debug, player::PlayerInput, warn};
+
+use super::{protocol::{self, ClientPacket, ServerPacket, MAX_PACKET_SIZE}, server::ServerHandle, CONNECTION_TIMEOUT};
+
+/// One client's connection to a server
+pub struct Client {
+    socket: UdpSocket,
+    server: SocketAddr,
+    last_heard: Instant,
+    // the server when this game is hosting it, stopped once the client is done with it
+    host: Option<ServerHandle>,
+}
+
+impl Client {
+    /// open a socket for talking to `server`, nothing is sent until the caller asks to connect
+    pub fn new(server: SocketAddr) -> Result<Self, String> {
+        // let the OS pick a port on an interface that can reach the server
+        let local: SocketAddr = match server {
+            SocketAddr::V4(addr) if addr.ip().is_loopback() => (Ipv4Addr::LOCALHOST, 0).into(),
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(addr) if addr.ip().is_loopback() => (Ipv6Addr::LOCALHOST, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = UdpSocket::bind(local)
+            .map_err(|e| format!("Failed to open client socket: {}", e))?;
+        socket.set_nonblocking(true)
+            .map_err(|e| format!("Failed to configure client socket: {}", e))?;
+
+        Ok(Self {
+            socket,
+            server,
+            last_heard: Instant::now(),
+            host: None,
+        })
+    }
+
+    /// a client for a server this game is hosting, the server runs for as long as the client does
+    pub fn hosting(server: ServerHandle) -> Result<Self, String> {
+        let mut client = Self::new(server.addr())?;
+        client.host = Some(server);
+        Ok(client)
+    }
+
+    pub fn is_hosting(&self) -> bool {
+        self.host.is_some()
+    }
+
+    pub fn server(&self) -> SocketAddr {
+        self.server
+    }
+
+    pub fn send(&self, packet: &ClientPacket) {
+        let bytes = match protocol::encode(packet) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("{}", e);
+                return;
+            }
+        };
+        if let Err(e) = self.socket.send_to(&bytes, self.server) {
+            debug!("Failed to send packet to {}: {}", self.server, e);
+        }
+    }
+
+    /// every packet the server has sent since the last poll
+    pub fn poll(&mut self) -> Vec<ServerPacket> {
+        let mut packets = Vec::new();
+        let mut buffer = vec![0; MAX_PACKET_SIZE];
+        loop {
+            let (len, addr) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                // the server isn't up (yet), the timeout takes care of it
+                Err(e) if e.kind() == ErrorKind::ConnectionReset || e.kind() == ErrorKind::ConnectionRefused => continue,
+                Err(e) => {
+                    warn!("Failed to receive packet: {}", e);
+                    break;
+                }
+            };
+            if addr != self.server {
+                continue;
+            }
+
+            match protocol::decode::<ServerPacket>(&buffer[..len]) {
+                Ok(packet) => {
+                    self.last_heard = Instant::now();
+                    packets.push(packet);
+                }
+                Err(e) => {
+                    debug!("Ignoring packet from {}: {}", addr, e);
+                }
+            }
+        }
+        packets
+    }
+
+    /// true once the server has been silent for longer than the connection timeout
+    pub fn timed_out(&self) -> bool {
+        self.last_heard.elapsed() > CONNECTION_TIMEOUT
+    }
+}
+
+/// A connection the server has welcomed us on, with what it told us about itself
+pub struct Session {
+    pub client: Client,
+    pub player_id: u32,
+    pub bounds: Vec2,
+    pub tick_rate: u64,
+    // what we asked to fly, so the same aircraft is asked for when reconnecting
+    pub aircraft_id: String,
+    // the last input tick sent, which has to keep counting up for the server to accept inputs
+    tick: u64,
+}
+
+impl Session {
+    pub fn new(client: Client, player_id: u32, bounds: Vec2, tick_rate: u64, aircraft_id: &str) -> Self {
+        Self {
+            client,
+            player_id,
+            bounds,
+            tick_rate,
+            aircraft_id: aircraft_id.to_string(),
+            tick: 0,
+        }
+    }
+
+    /// seconds between server steps
+    pub fn server_timestep(&self) -> f32 {
+        1.0 / self.tick_rate.max(1) as f32
+    }
+
+    /// send the input for this step, which also lets the server know we are still here
+    pub fn send_input(&mut self, input: PlayerInput) {
+        self.tick += 1;
+        self.client.send(&ClientPacket::Input { tick: self.tick, input });
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        // let the server free our aircraft straight away instead of waiting for the timeout
+        self.send(&ClientPacket::Disconnect);
+    }
+}
@@ -0,0 +1,16 @@
+use std::time::Duration;
+
+use macroquad::math::Vec2;
+
+pub mod client;
+pub mod protocol;
+pub mod server;
+
+pub const DEFAULT_PORT: u16 = 27015;
+
+// a connection that hasn't been heard from for this long is considered lost
+pub const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+// the server simulates a fixed size world so every client sees the same thing regardless of window size,
+// this is the size used when the server config doesn't set one. clients scale it to fit their window
+pub const WORLD_BOUNDS: Vec2 = Vec2::new(1200.0, 800.0);
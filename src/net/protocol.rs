@@ -0,0 +1,329 @@
+use macroquad::math::Vec2;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{aircraft::Aircraft, boss::{Boss, BossDef}, player::{Player, PlayerInput}, pvp::MatchStatus, waves::WaveStatus, weapons::{gun::Bullet, missile::Missile}, world::World};
+
+// every packet starts with this so stray datagrams from other programs are ignored
+const MAGIC: &[u8; 4] = b"MRLN";
+// bumped whenever the packets below change, clients and servers must match
+pub const PROTOCOL_VERSION: u32 = 4;
+// the largest datagram that fits in a single UDP packet
+pub const MAX_PACKET_SIZE: usize = 65_507;
+
+// the most of each thing a snapshot carries, so it always fits in a packet however busy the world gets.
+// anything past these isn't drawn by clients until there is room for it again
+pub const MAX_SNAPSHOT_AIRCRAFT: usize = 64;
+const MAX_SNAPSHOT_BOSSES: usize = 8;
+const MAX_SNAPSHOT_SHOTS: usize = 128;
+const MAX_SNAPSHOT_MISSILES: usize = 32;
+
+/// Packets sent from a client to the server
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum ClientPacket {
+    // sent repeatedly until the server welcomes or rejects the client
+    Connect { version: u32, aircraft: String },
+    // the input for one client step, also keeps the connection alive
+    Input { tick: u64, input: PlayerInput },
+    Disconnect,
+}
+
+/// Packets sent from the server to a client
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum ServerPacket {
+    Welcome { player_id: u32, bounds: [f32; 2], tick_rate: u64 },
+    Rejected { reason: String },
+    Snapshot(Snapshot),
+    Disconnect { reason: String },
+}
+
+pub fn encode<T: Serialize>(packet: &T) -> Result<Vec<u8>, String> {
+    let mut bytes = MAGIC.to_vec();
+    serde_json::to_writer(&mut bytes, packet).map_err(|e| format!("Failed to encode packet: {}", e))?;
+    if bytes.len() > MAX_PACKET_SIZE {
+        return Err(format!("Packet is too large to send ({} bytes)", bytes.len()));
+    }
+    Ok(bytes)
+}
+
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    let body = bytes.strip_prefix(MAGIC).ok_or_else(|| "Packet is missing the protocol header".to_string())?;
+    serde_json::from_slice(body).map_err(|e| format!("Failed to decode packet: {}", e))
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PlayerSnapshot {
+    pub id: u32,
+    pub team: u32,
+    pub aircraft: String,
+    pub pos: [f32; 2],
+    pub rotation: f32,
+    pub turn_rate: f32,
+    pub speed: f32,
+    pub throttle_percent: f32,
+    pub health: u32,
+    pub airbrake: bool,
+    pub spawn_protection: f32,
+    pub ammo: u32,
+    pub missiles: u32,
+    pub seeker_candidate: Option<u32>,
+    pub seeker_locked: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BossSnapshot {
+    pub id: u32,
+    pub team: u32,
+    pub boss: String,
+    pub pos: [f32; 2],
+    pub rotation: f32,
+    pub phase: usize,
+    pub component_health: Vec<u32>,
+}
+
+/// A bullet fired during the step, clients fly it themselves from then on
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ShotSnapshot {
+    pub team: u32,
+    pub pos: [f32; 2],
+    pub velocity: [f32; 2],
+    pub lifetime: f32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MissileSnapshot {
+    pub pos: [f32; 2],
+    pub prev_pos: [f32; 2],
+    pub rotation: f32,
+    pub prev_rotation: f32,
+}
+
+/// Everything a client needs to draw one simulation step of the server's world
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub players: Vec<PlayerSnapshot>,
+    pub bosses: Vec<BossSnapshot>,
+    pub shots: Vec<ShotSnapshot>,
+    pub missiles: Vec<MissileSnapshot>,
+    // depending on the game mode the server is running
+    pub waves: Option<WaveStatus>,
+    pub pvp: Option<MatchStatus>,
+}
+
+impl Snapshot {
+    pub fn capture(tick: u64, world: &World, waves: Option<WaveStatus>, pvp: Option<MatchStatus>) -> Self {
+        Self {
+            tick,
+            // players flown by people always make it in, AI aircraft fill whatever room is left
+            players: world.players.iter().filter(|p| p.pilot.is_none())
+                .chain(world.players.iter().filter(|p| p.pilot.is_some()))
+                .take(MAX_SNAPSHOT_AIRCRAFT)
+                .map(|p| PlayerSnapshot {
+                id: p.id,
+                team: p.team,
+                aircraft: p.aircraft.id.clone(),
+                pos: p.pos.into(),
+                rotation: p.rotation,
+                turn_rate: p.turn_rate,
+                speed: p.speed,
+                throttle_percent: p.throttle_percent,
+                health: p.health,
+                airbrake: p.airbrake,
+                spawn_protection: p.spawn_protection,
+                ammo: p.gun.ammo,
+                missiles: p.launcher.missiles,
+                seeker_candidate: p.seeker.candidate,
+                seeker_locked: p.seeker.locked,
+            }).collect(),
+            bosses: world.bosses.iter().take(MAX_SNAPSHOT_BOSSES).map(|b| BossSnapshot {
+                id: b.id,
+                team: b.team,
+                boss: b.def.id.clone(),
+                pos: b.pos.into(),
+                rotation: b.rotation,
+                phase: b.phase,
+                component_health: b.components.iter().map(|c| c.health).collect(),
+            }).collect(),
+            shots: world.shots.iter().take(MAX_SNAPSHOT_SHOTS).map(|b| ShotSnapshot {
+                team: b.team,
+                pos: b.pos.into(),
+                velocity: b.velocity.into(),
+                lifetime: b.lifetime,
+            }).collect(),
+            missiles: world.missiles.iter().take(MAX_SNAPSHOT_MISSILES).map(|m| MissileSnapshot {
+                pos: m.pos.into(),
+                prev_pos: m.prev_pos.into(),
+                rotation: m.rotation,
+                prev_rotation: m.prev_rotation,
+            }).collect(),
+            waves,
+            pvp,
+        }
+    }
+
+    /// update a client's copy of the world to match the snapshot, `elapsed` seconds after the last one applied.
+    /// aircraft and bosses that were already in the world keep their last position as the one to interpolate from
+    pub fn apply(&self, world: &mut World, roster: &[Aircraft], boss_roster: &[BossDef], elapsed: f32) {
+        let mut players = Vec::with_capacity(self.players.len());
+        for snapshot in &self.players {
+            let previous = world.player(snapshot.id);
+            let mut player = match previous {
+                Some(previous) if previous.aircraft.id == snapshot.aircraft => previous.clone(),
+                _ => {
+                    let Some(aircraft) = roster.iter().find(|a| a.id == snapshot.aircraft) else {
+                        continue;
+                    };
+                    let mut player = Player::new(snapshot.id, snapshot.team, aircraft.clone());
+                    player.pos = snapshot.pos.into();
+                    player.rotation = snapshot.rotation;
+                    player
+                }
+            };
+
+            player.prev_pos = player.pos;
+            player.prev_rotation = player.rotation;
+            player.team = snapshot.team;
+            player.pos = snapshot.pos.into();
+            player.rotation = snapshot.rotation;
+            player.turn_rate = snapshot.turn_rate;
+            player.speed = snapshot.speed;
+            player.throttle_percent = snapshot.throttle_percent;
+            player.health = snapshot.health;
+            player.airbrake = snapshot.airbrake;
+            player.spawn_protection = snapshot.spawn_protection;
+            player.gun.ammo = snapshot.ammo;
+            player.launcher.missiles = snapshot.missiles;
+            player.seeker.candidate = snapshot.seeker_candidate;
+            player.seeker.locked = snapshot.seeker_locked;
+            players.push(player);
+        }
+        world.players = players;
+
+        let mut bosses = Vec::with_capacity(self.bosses.len());
+        for snapshot in &self.bosses {
+            let mut boss = match world.boss(snapshot.id) {
+                Some(previous) => previous.clone(),
+                None => {
+                    let Some(def) = boss_roster.iter().find(|b| b.id == snapshot.boss) else {
+                        continue;
+                    };
+                    Boss::new(snapshot.id, snapshot.team, def.clone(), snapshot.pos.into(), snapshot.rotation)
+                }
+            };
+
+            boss.prev_pos = boss.pos;
+            boss.prev_rotation = boss.rotation;
+            boss.pos = snapshot.pos.into();
+            boss.rotation = snapshot.rotation;
+            boss.phase = snapshot.phase.min(boss.def.phases.len() - 1);
+            for (component, health) in boss.components.iter_mut().zip(&snapshot.component_health) {
+                component.health = *health;
+            }
+            bosses.push(boss);
+        }
+        world.bosses = bosses;
+
+        // bullets are only sent when fired, so fly the ones we already know about up to now
+        world.advance_bullets(elapsed);
+        world.bullets.extend(self.shots.iter().map(|b| {
+            let pos = Vec2::from(b.pos);
+            // only drawn on the client, so who fired it and how much it hurts don't matter
+            Bullet {
+                owner: 0,
+                team: b.team,
+                pos,
+                prev_pos: pos,
+                velocity: b.velocity.into(),
+                lifetime: b.lifetime,
+                damage: 0,
+            }
+        }));
+
+        world.missiles = self.missiles.iter().map(|m| {
+            let mut missile = Missile::launch(0, 0, m.pos.into(), m.rotation, 0.0, None, Default::default());
+            missile.prev_pos = m.prev_pos.into();
+            missile.prev_rotation = m.prev_rotation;
+            missile
+        }).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use macroquad::math::vec2;
+
+    use super::*;
+    use crate::{ai::AiPilot, aircraft::AIRCRAFT_DIR, boss::BOSS_DIR, controls::Action, net::{client::Client, server::{Server, ServerConfig}}, waves::{WaveTable, WAVES_PATH}, world::DRONE_TEAM};
+
+    #[test]
+    fn packets_survive_a_round_trip() {
+        let input = PlayerInput { roll: -0.5, throttle_target: 87.5, actions: vec![Action::FireGun, Action::Airbrake] };
+        let bytes = encode(&ClientPacket::Input { tick: 42, input: input.clone() }).unwrap();
+        match decode::<ClientPacket>(&bytes).unwrap() {
+            ClientPacket::Input { tick, input: decoded } => assert_eq!((tick, decoded), (42, input)),
+            packet => panic!("decoded the wrong packet: {:?}", packet),
+        }
+
+        let bytes = encode(&ServerPacket::Welcome { player_id: 3, bounds: [1200.0, 800.0], tick_rate: 60 }).unwrap();
+        match decode::<ServerPacket>(&bytes).unwrap() {
+            ServerPacket::Welcome { player_id, bounds, tick_rate } => assert_eq!((player_id, bounds, tick_rate), (3, [1200.0, 800.0], 60)),
+            packet => panic!("decoded the wrong packet: {:?}", packet),
+        }
+    }
+
+    #[test]
+    fn packets_without_the_header_are_rejected() {
+        let bytes = encode(&ClientPacket::Disconnect).unwrap();
+        assert!(decode::<ClientPacket>(&bytes[MAGIC.len()..]).is_err());
+        assert!(decode::<ClientPacket>(b"MRLNnot json").is_err());
+    }
+
+    #[test]
+    fn server_rejects_other_protocol_versions() {
+        let config = ServerConfig {
+            bind: "127.0.0.1:0".parse().unwrap(),
+            ..Default::default()
+        };
+        let roster = Aircraft::load_all(AIRCRAFT_DIR).unwrap();
+        let boss_roster = BossDef::load_all(BOSS_DIR).unwrap();
+        let mut server = Server::bind(&config, roster, boss_roster, WaveTable::load(WAVES_PATH).unwrap()).unwrap();
+        let mut client = Client::new(server.local_addr().unwrap()).unwrap();
+
+        client.send(&ClientPacket::Connect { version: PROTOCOL_VERSION + 1, aircraft: "f16".to_string() });
+        let mut reply = None;
+        for _ in 0..100 {
+            server.step(&server.timestep());
+            reply = client.poll().into_iter().next();
+            if reply.is_some() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        assert!(matches!(reply, Some(ServerPacket::Rejected { .. })));
+        assert_eq!(server.player_count(), 0);
+    }
+
+    #[test]
+    fn crowded_snapshot_fits_in_a_packet() {
+        let aircraft = Aircraft::load(&Path::new(AIRCRAFT_DIR).join("f16.json")).unwrap();
+        let boss = BossDef::load_all(BOSS_DIR).unwrap().remove(0);
+        let mut world = World::new(vec2(1200.0, 800.0));
+        for i in 0..500 {
+            let id = world.spawn_player(aircraft.clone(), DRONE_TEAM, vec2(i as f32 * 1.7, 400.123), 1.234);
+            world.player_mut(id).unwrap().pilot = Some(AiPilot::default());
+            world.shots.push(Bullet::fired_from(world.player(id).unwrap(), 0.0123));
+            world.missiles.push(Missile::launch(id, DRONE_TEAM, vec2(100.123, 200.456), 0.789, 300.123, Some(0), Default::default()));
+        }
+        for _ in 0..50 {
+            world.spawn_boss(boss.clone(), DRONE_TEAM, vec2(600.123, 400.456), 2.345);
+        }
+        let human = world.spawn_player(aircraft, 0, vec2(1.0, 1.0), 0.0);
+
+        let snapshot = Snapshot::capture(u64::MAX, &world, None, None);
+        assert!(snapshot.players.iter().any(|p| p.id == human));
+        assert!(encode(&ServerPacket::Snapshot(snapshot)).is_ok());
+    }
+}
@@ -0,0 +1,436 @@
+use std::{collections::HashMap, io::ErrorKind, net::{Ipv4Addr, SocketAddr, UdpSocket}, path::Path, sync::{atomic::{AtomicBool, Ordering}, Arc}, thread::{self, JoinHandle}, time::{Duration, Instant}};
+
+use macroquad::{math::{vec2, Vec2}, rand::gen_range};
+use serde::{Deserialize, Serialize};
+
+use crate::{aircraft::{Aircraft, AIRCRAFT_DIR}, boss::{BossDef, BOSS_DIR}, debug, info, pvp::{Match, MatchRules}, warn, waves::{WaveDirector, WaveTable, WAVES_PATH}, world::{World, FIRST_FREE_FOR_ALL_TEAM, PLAYER_TEAM}, TICK_RATE};
+
+use super::{protocol::{self, ClientPacket, ServerPacket, Snapshot, MAX_PACKET_SIZE, MAX_SNAPSHOT_AIRCRAFT, PROTOCOL_VERSION}, CONNECTION_TIMEOUT, DEFAULT_PORT, WORLD_BOUNDS};
+
+pub const SERVER_CONFIG_PATH: &str = "./data/server.json";
+
+// in waves mode destroyed players sit out this many seconds before rejoining the fight
+const WAVES_RESPAWN_DELAY: f32 = 5.0;
+// and can't be damaged for this long after they do
+const WAVES_SPAWN_PROTECTION: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum GameMode {
+    // everyone on the same team against waves of drones
+    Waves,
+    // free for all dogfight, scored by kills
+    Pvp,
+}
+
+/// How a server is run, read from a file with anything missing left at its default
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub bind: SocketAddr,
+    // simulation steps per second, clients are told so they can interpolate at the right rate
+    pub tick_rate: u64,
+    pub max_players: usize,
+    // width and height of the world in pixels, sent to clients when they connect
+    pub bounds: [f32; 2],
+    pub mode: GameMode,
+    // only used in pvp mode
+    pub pvp: MatchRules,
+    pub aircraft_dir: String,
+    pub boss_dir: String,
+    pub waves: String,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: (Ipv4Addr::UNSPECIFIED, DEFAULT_PORT).into(),
+            tick_rate: TICK_RATE,
+            max_players: 8,
+            bounds: WORLD_BOUNDS.into(),
+            mode: GameMode::Waves,
+            pvp: MatchRules::default(),
+            aircraft_dir: AIRCRAFT_DIR.to_string(),
+            boss_dir: BOSS_DIR.to_string(),
+            waves: WAVES_PATH.to_string(),
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let config: ServerConfig = serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.tick_rate == 0 || self.tick_rate > 1000 {
+            return Err(format!("Tick rate must be between 1 and 1000, got {}", self.tick_rate));
+        }
+        if self.max_players == 0 || self.max_players > MAX_SNAPSHOT_AIRCRAFT {
+            return Err(format!("Max players must be between 1 and {}, got {}", MAX_SNAPSHOT_AIRCRAFT, self.max_players));
+        }
+        if !self.bounds.iter().all(|size| size.is_finite() && *size > 0.0) {
+            return Err(format!("World bounds must be positive, got {:?}", self.bounds));
+        }
+        if self.pvp.time_limit < 0.0 || self.pvp.respawn_delay < 0.0 || self.pvp.spawn_protection < 0.0 {
+            return Err("Match times can't be negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A client the server has welcomed
+#[derive(Debug, Clone)]
+struct Connection {
+    player_id: u32,
+    last_heard: Instant,
+    // the newest input tick received, older inputs arriving out of order are ignored
+    last_input: u64,
+}
+
+/// Runs the authoritative simulation and keeps every connected client in sync with it
+pub struct Server {
+    socket: UdpSocket,
+    world: World,
+    // what is being played depends on the game mode
+    waves: Option<WaveDirector>,
+    pvp: Option<Match>,
+    // seconds until each destroyed player respawns, in waves mode
+    respawns: HashMap<u32, f32>,
+    roster: Vec<Aircraft>,
+    connections: HashMap<SocketAddr, Connection>,
+    tick_rate: u64,
+    max_players: usize,
+    tick: u64,
+}
+
+impl Server {
+    pub fn bind(config: &ServerConfig, roster: Vec<Aircraft>, boss_roster: Vec<BossDef>, waves: WaveTable) -> Result<Self, String> {
+        config.validate()?;
+        let socket = UdpSocket::bind(config.bind)
+            .map_err(|e| format!("Failed to bind server to {}: {}", config.bind, e))?;
+        socket.set_nonblocking(true)
+            .map_err(|e| format!("Failed to configure server socket: {}", e))?;
+
+        let (waves, pvp) = match config.mode {
+            GameMode::Waves => (Some(WaveDirector::new(waves, &roster, &boss_roster)?), None),
+            GameMode::Pvp => (None, Some(Match::new(config.pvp.clone()))),
+        };
+
+        Ok(Self {
+            socket,
+            world: World::new(config.bounds.into()),
+            waves,
+            pvp,
+            respawns: HashMap::new(),
+            roster,
+            connections: HashMap::new(),
+            tick_rate: config.tick_rate,
+            max_players: config.max_players,
+            tick: 0,
+        })
+    }
+
+    /// load everything the simulation needs from the paths in `config` and bind
+    pub fn from_config(config: &ServerConfig) -> Result<Self, String> {
+        let roster = Aircraft::load_all(&config.aircraft_dir)?;
+        let boss_roster = BossDef::load_all(&config.boss_dir)?;
+        let waves = WaveTable::load(&config.waves)?;
+        Self::bind(config, roster, boss_roster, waves)
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.connections.len()
+    }
+
+    pub fn timestep(&self) -> Duration {
+        Duration::from_nanos(1_000_000_000 / self.tick_rate)
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, String> {
+        self.socket.local_addr().map_err(|e| e.to_string())
+    }
+
+    /// step the server once: read what the clients sent, simulate, and send everyone the result
+    pub fn step(&mut self, delta_time: &Duration) {
+        self.receive();
+        self.drop_timed_out();
+
+        // everything stops while the results of a match are up
+        let frozen = self.pvp.as_ref().is_some_and(|m| m.is_finished());
+        let hits = if frozen { Vec::new() } else { self.world.update(delta_time) };
+        if let Some(waves) = &mut self.waves {
+            waves.update(&mut self.world, delta_time);
+            self.respawn_destroyed(delta_time);
+        }
+        if let Some(pvp) = &mut self.pvp {
+            pvp.update(&mut self.world, &hits, delta_time);
+        }
+        self.tick += 1;
+
+        let snapshot = Snapshot::capture(
+            self.tick,
+            &self.world,
+            self.waves.as_ref().map(|w| w.status()),
+            self.pvp.as_ref().map(|m| m.status()),
+        );
+        let snapshot = ServerPacket::Snapshot(snapshot);
+        self.broadcast(&snapshot);
+    }
+
+    /// step at a fixed rate until `stop` is set or `max_ticks` steps have run,
+    /// then tell everyone the server is going away
+    pub fn run(&mut self, stop: &AtomicBool, max_ticks: Option<u64>) {
+        let timestep = self.timestep();
+        let mut next_step = Instant::now();
+        while !stop.load(Ordering::Relaxed) && max_ticks.is_none_or(|max| self.tick < max) {
+            self.step(&timestep);
+
+            next_step += timestep;
+            let now = Instant::now();
+            if next_step > now {
+                thread::sleep(next_step - now);
+            } else {
+                // fell behind, don't try to catch up with a burst of steps
+                next_step = now;
+            }
+        }
+
+        self.broadcast(&ServerPacket::Disconnect { reason: "Server shutting down".to_string() });
+    }
+
+    /// bring players destroyed by the drones back once they have waited long enough
+    fn respawn_destroyed(&mut self, delta_time: &Duration) {
+        let dt = delta_time.as_secs_f32();
+        let destroyed: Vec<u32> = self.connections.values()
+            .map(|c| c.player_id)
+            .filter(|id| self.world.player(*id).is_some_and(|p| !p.is_alive()))
+            .collect();
+        self.respawns.retain(|id, _| destroyed.contains(id));
+
+        for id in destroyed {
+            let remaining = self.respawns.entry(id).or_insert(WAVES_RESPAWN_DELAY);
+            *remaining -= dt;
+            if *remaining > 0.0 {
+                continue;
+            }
+
+            self.respawns.remove(&id);
+            let (pos, rotation) = self.spawn_point();
+            self.world.respawn_player(id, pos, rotation);
+            if let Some(player) = self.world.player_mut(id) {
+                player.spawn_protection = WAVES_SPAWN_PROTECTION;
+            }
+        }
+    }
+
+    /// a random position and heading away from the edges of the world
+    fn spawn_point(&self) -> (Vec2, f32) {
+        let bounds = self.world.bounds;
+        let pos = vec2(gen_range(0.2, 0.8) * bounds.x, gen_range(0.2, 0.8) * bounds.y);
+        (pos, gen_range(0.0, std::f32::consts::TAU))
+    }
+
+    fn receive(&mut self) {
+        let mut buffer = vec![0; MAX_PACKET_SIZE];
+        loop {
+            let (len, addr) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                // windows reports a client going away as an error on the next read, nothing to do about it
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                Err(e) => {
+                    warn!("Failed to receive packet: {}", e);
+                    break;
+                }
+            };
+
+            match protocol::decode::<ClientPacket>(&buffer[..len]) {
+                Ok(packet) => self.handle_packet(addr, packet),
+                Err(e) => {
+                    debug!("Ignoring packet from {}: {}", addr, e);
+                }
+            }
+        }
+    }
+
+    fn handle_packet(&mut self, addr: SocketAddr, packet: ClientPacket) {
+        if let Some(connection) = self.connections.get_mut(&addr) {
+            connection.last_heard = Instant::now();
+        }
+
+        match packet {
+            ClientPacket::Connect { version, aircraft } => self.connect(addr, version, &aircraft),
+            ClientPacket::Input { tick, input } => {
+                let Some(connection) = self.connections.get_mut(&addr) else {
+                    return;
+                };
+                if tick < connection.last_input {
+                    return;
+                }
+                connection.last_input = tick;
+                let player_id = connection.player_id;
+                if let Some(player) = self.world.player_mut(player_id) {
+                    player.input = input;
+                }
+            }
+            ClientPacket::Disconnect => {
+                if let Some(connection) = self.connections.remove(&addr) {
+                    info!("{} disconnected", addr);
+                    self.remove_player(connection.player_id);
+                }
+            }
+        }
+    }
+
+    fn connect(&mut self, addr: SocketAddr, version: u32, aircraft: &str) {
+        // the client keeps asking until it hears back, so the welcome may have been lost
+        if let Some(connection) = self.connections.get(&addr) {
+            let welcome = ServerPacket::Welcome { player_id: connection.player_id, bounds: self.world.bounds.into(), tick_rate: self.tick_rate };
+            self.send(addr, &welcome);
+            return;
+        }
+
+        let rejection = if version != PROTOCOL_VERSION {
+            Some(format!("Server is running protocol version {}, client is {}", PROTOCOL_VERSION, version))
+        } else if self.connections.len() >= self.max_players {
+            Some("Server is full".to_string())
+        } else if !self.roster.iter().any(|a| a.id == aircraft) {
+            Some(format!("Server doesn't know aircraft '{}'", aircraft))
+        } else {
+            None
+        };
+        if let Some(reason) = rejection {
+            info!("Rejected {}: {}", addr, reason);
+            self.send(addr, &ServerPacket::Rejected { reason });
+            return;
+        }
+
+        let Some(aircraft) = self.roster.iter().find(|a| a.id == aircraft).cloned() else {
+            return;
+        };
+        let (pos, rotation) = self.spawn_point();
+        let player_id = self.world.spawn_player(aircraft, PLAYER_TEAM, pos, rotation);
+        if let Some(pvp) = &mut self.pvp {
+            // everyone is an enemy in a free for all
+            if let Some(player) = self.world.player_mut(player_id) {
+                player.team = FIRST_FREE_FOR_ALL_TEAM + player_id;
+            }
+            pvp.join(&mut self.world, player_id, format!("Pilot {}", player_id + 1));
+        }
+
+        self.connections.insert(addr, Connection {
+            player_id,
+            last_heard: Instant::now(),
+            last_input: 0,
+        });
+        info!("{} connected as player {}", addr, player_id);
+        self.send(addr, &ServerPacket::Welcome { player_id, bounds: self.world.bounds.into(), tick_rate: self.tick_rate });
+    }
+
+    fn remove_player(&mut self, player_id: u32) {
+        self.world.despawn(&[player_id]);
+        if let Some(pvp) = &mut self.pvp {
+            pvp.leave(player_id);
+        }
+    }
+
+    fn drop_timed_out(&mut self) {
+        let timed_out: Vec<SocketAddr> = self.connections.iter()
+            .filter(|(_, c)| c.last_heard.elapsed() > CONNECTION_TIMEOUT)
+            .map(|(addr, _)| *addr)
+            .collect();
+
+        for addr in timed_out {
+            if let Some(connection) = self.connections.remove(&addr) {
+                info!("{} timed out", addr);
+                self.remove_player(connection.player_id);
+                // in case it can still hear us
+                self.send(addr, &ServerPacket::Disconnect { reason: "Timed out".to_string() });
+            }
+        }
+    }
+
+    fn send(&self, addr: SocketAddr, packet: &ServerPacket) {
+        let bytes = match protocol::encode(packet) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("{}", e);
+                return;
+            }
+        };
+        if let Err(e) = self.socket.send_to(&bytes, addr) {
+            debug!("Failed to send packet to {}: {}", addr, e);
+        }
+    }
+
+    fn broadcast(&self, packet: &ServerPacket) {
+        for addr in self.connections.keys() {
+            self.send(*addr, packet);
+        }
+    }
+}
+
+/// A server running on its own thread for the lifetime of the handle, used to host a game from the client
+pub struct ServerHandle {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    /// run a server on this machine with the same rules a dedicated server would use, but only for this machine
+    pub fn host_locally(roster: &[Aircraft], boss_roster: &[BossDef]) -> Result<Self, String> {
+        let mut config = if Path::new(SERVER_CONFIG_PATH).exists() {
+            ServerConfig::load(SERVER_CONFIG_PATH)
+                .map_err(|e| format!("Failed to load server config: {}", e))?
+        } else {
+            ServerConfig::default()
+        };
+        config.bind = SocketAddr::from((Ipv4Addr::LOCALHOST, DEFAULT_PORT));
+
+        let waves = WaveTable::load(&config.waves)?;
+        let server = Server::bind(&config, roster.to_vec(), boss_roster.to_vec(), waves)
+            .map_err(|e| format!("Failed to start server: {}", e))?;
+        Self::spawn(server)
+    }
+
+    pub fn spawn(mut server: Server) -> Result<Self, String> {
+        let addr = server.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_stop = stop.clone();
+        let thread = thread::Builder::new()
+            .name("merlin-server".to_string())
+            .spawn(move || server.run(&thread_stop, None))
+            .map_err(|e| format!("Failed to start server thread: {}", e))?;
+
+        Ok(Self {
+            addr,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
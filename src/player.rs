@@ -1,23 +1,57 @@
 use std::time::Duration;
 
-use macroquad::{math::{vec2, Vec2}, window::{screen_height, screen_width}};
+use macroquad::math::{vec2, Vec2};
+use serde::{Deserialize, Serialize};
 
-use crate::aircraft::{Aircraft, AircraftType};
+use crate::{ai::AiPilot, aircraft::Aircraft, collision::Hitbox, controls::Action, math, weapons::{gun::Gun, missile::{Launcher, Seeker}}};
 
 // for edge detection
 const EDGE_BOUNDS: f32 = 30.0;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub enum PlayerState {
-    Normal,
-    TurningRight,
-    TurningLeft,
+// how many screen pixels one meter covers
+pub const PIXELS_PER_METER: f32 = 2.0;
+// how fast the engine follows the throttle target, in percent per second
+pub const ENGINE_RESPONSE: f32 = 120.0;
+// throttle percent a fresh aircraft starts at
+pub const START_THROTTLE: f32 = 60.0;
+
+/// What whoever is flying an aircraft wants it to do, applied every simulation step
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PlayerInput {
+    // -1.0 rolls fully left and 1.0 fully right, turning and turn drag scale with it
+    pub roll: f32,
+    // throttle percent the engine is working towards, the engine follows it at ENGINE_RESPONSE
+    pub throttle_target: f32,
+    // everything else being held, i.e. the weapons and airbrake
+    pub actions: Vec<Action>,
+}
+
+impl PlayerInput {
+    pub fn holds(&self, action: Action) -> bool {
+        self.actions.contains(&action)
+    }
+}
+
+impl Default for PlayerInput {
+    fn default() -> Self {
+        Self {
+            roll: 0.0,
+            throttle_target: START_THROTTLE,
+            actions: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Player {
-    pub pos: Vec2,
+    // unique within the world the player is in
+    pub id: u32,
+    pub team: u32,
+    pub pos: Vec2, // center of the aircraft
     pub rotation: f32,
+    // position and rotation at the previous simulation step, used for render interpolation
+    pub prev_pos: Vec2,
+    pub prev_rotation: f32,
 
     pub turn_rate: f32,
     pub speed: f32,
@@ -25,30 +59,49 @@ pub struct Player {
 
     pub health: u32,
     pub airbrake: bool,
+    // seconds left where the aircraft can't be damaged after spawning
+    pub spawn_protection: f32,
 
     pub aircraft: Aircraft,
-
-    pub state: PlayerState,
+    pub gun: Gun,
+    pub launcher: Launcher,
+    pub seeker: Seeker,
+
+    // from whoever is flying this aircraft, applied every simulation step
+    pub input: PlayerInput,
+    // how hard the aircraft is rolling from the last input, -1.0 to 1.0
+    pub roll: f32,
+    // computer controlled aircraft have their input decided by a pilot each step
+    pub pilot: Option<AiPilot>,
 }
 
 impl Player {
-    pub async fn new() -> Result<Self, String> {
-        let aircraft = AircraftType::GripenE.get_aircraft().await?;
-        Ok(Self {
+    pub fn new(id: u32, team: u32, aircraft: Aircraft) -> Self {
+        Self {
+            id,
+            team,
             pos: vec2(0.0, 0.0),
             rotation: 0.0,
+            prev_pos: vec2(0.0, 0.0),
+            prev_rotation: 0.0,
 
-            turn_rate: 0.0004,
+            turn_rate: 0.0,
             speed: 120.0,
-            throttle_percent: 60.0,
+            throttle_percent: START_THROTTLE,
 
             health: aircraft.max_health,
             airbrake: false,
+            spawn_protection: 0.0,
 
-            aircraft: aircraft,
+            gun: Gun::new(&aircraft),
+            launcher: Launcher::new(aircraft.missile_capacity),
+            seeker: Seeker::default(),
+            aircraft,
 
-            state: PlayerState::Normal,
-        })
+            input: PlayerInput::default(),
+            roll: 0.0,
+            pilot: None,
+        }
     }
 
     pub fn set_throttle(&mut self, throttle_percent: f32) {
@@ -63,14 +116,13 @@ impl Player {
             drag_coefficient += self.aircraft.airbrake_drag;
         }
 
-        // turning
-        if self.state == PlayerState::TurningLeft || self.state == PlayerState::TurningRight {
-            drag_coefficient *= self.aircraft.turn_drag;
-        }
+        // turning, a gentle roll only costs some of the drag of a full one
+        drag_coefficient *= 1.0 + (self.aircraft.turn_drag - 1.0) * self.roll.abs();
 
-        return drag_coefficient;
+        drag_coefficient
     }
 
+    // in m/s^2
     pub fn get_acceleration(&self) -> f32 {
         let mut acc = Aircraft::calculate_acceleration(self.aircraft.thrust_curve.clone(),
          self.throttle_percent as u32, self.aircraft.thrust_multiplier, self.speed, self.get_drag_coefficient(),
@@ -84,13 +136,19 @@ impl Player {
         acc
     }
 
+    /// in newtons
+    pub fn get_drag(&self) -> f32 {
+        0.5 * self.get_drag_coefficient() * self.aircraft.reference_area * self.speed * self.speed
+    }
+
+    /// in newtons
     pub fn get_thrust(&self) -> f32 {
-        self.aircraft.thrust_curve.get_thrust(self.throttle_percent as u32, self.aircraft.thrust_multiplier) as f32
+        self.aircraft.thrust_curve.get_thrust(self.throttle_percent as u32, self.aircraft.thrust_multiplier)
     }
 
     fn update_speed(&mut self, delta_time: f32) {
         let acc = self.get_acceleration();
-        self.speed += acc;
+        self.speed += acc * delta_time;
         let stall_speed = self.aircraft.stall_speed;
         if self.speed < stall_speed {
             self.speed = stall_speed;
@@ -101,31 +159,102 @@ impl Player {
         self.turn_rate = self.aircraft.calc_turn_rate(self.speed);
     }
 
-    fn apply_velocity(&mut self, delta_time: f32) {
-        let direction = vec2(self.rotation.sin(), self.rotation.cos());
-        let velocity = (direction * self.speed) * vec2(0.002, -0.002);
-        self.pos += velocity * delta_time;
+    /// unit vector the nose is pointing in, in screen space
+    pub fn direction(&self) -> Vec2 {
+        math::direction(self.rotation)
+    }
+
+    /// in pixels per second
+    pub fn velocity(&self) -> Vec2 {
+        self.direction() * self.speed * PIXELS_PER_METER
+    }
+
+    /// where the guns fire from
+    pub fn nose(&self) -> Vec2 {
+        self.pos + self.direction() * self.aircraft.hitbox_radius
+    }
+
+    pub fn hitbox(&self) -> Hitbox {
+        Hitbox::new(self.pos, self.aircraft.hitbox_radius)
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.health > 0
+    }
+
+    pub fn is_protected(&self) -> bool {
+        self.spawn_protection > 0.0
+    }
+
+    pub fn take_damage(&mut self, damage: u32) {
+        if self.is_protected() {
+            return;
+        }
+        self.health = self.health.saturating_sub(damage);
+    }
+
+    fn apply_velocity(&mut self, delta_time: f32, bounds: Vec2) {
+        self.pos += self.velocity() * delta_time;
 
-        // teleport when at edges TODO: this is not working properly for some reason
-        if self.pos.x > screen_width() + EDGE_BOUNDS {
+        // teleport when at edges
+        let pos_before_wrap = self.pos;
+        if self.pos.x > bounds.x + EDGE_BOUNDS {
             self.pos.x = -EDGE_BOUNDS;
         } else if self.pos.x < -EDGE_BOUNDS {
-            self.pos.x = screen_width() + EDGE_BOUNDS;
+            self.pos.x = bounds.x + EDGE_BOUNDS;
         }
         
-        if self.pos.y > screen_height() + EDGE_BOUNDS {
+        if self.pos.y > bounds.y + EDGE_BOUNDS {
             self.pos.y = -EDGE_BOUNDS;
         } else if self.pos.y < -EDGE_BOUNDS {
-            self.pos.y = screen_height() + EDGE_BOUNDS;
+            self.pos.y = bounds.y + EDGE_BOUNDS;
         }
+
+        // don't interpolate across the whole screen after teleporting
+        if self.pos != pos_before_wrap {
+            self.prev_pos = self.pos;
+        }
+    }
+
+    /// turn the input for this simulation step into aircraft controls
+    pub fn apply_input(&mut self, input: &PlayerInput, delta_time: &Duration) {
+        let delta_time = delta_time.as_secs_f32();
+
+        // input can come from the network, so don't trust it to be in range
+        let target = if input.throttle_target.is_finite() {
+            input.throttle_target.clamp(0.0, self.aircraft.max_throttle())
+        } else {
+            self.throttle_percent
+        };
+        let step = ENGINE_RESPONSE * delta_time;
+        self.set_throttle(self.throttle_percent + (target - self.throttle_percent).clamp(-step, step));
+
+        self.roll = if input.roll.is_finite() { input.roll.clamp(-1.0, 1.0) } else { 0.0 };
+
+        self.airbrake = input.holds(Action::Airbrake);
     }
 
-    pub fn apply_action(&mut self, new_state: PlayerState) {
-        self.state = new_state;
+    /// position and rotation blended between the last two simulation steps
+    pub fn interpolated(&self, alpha: f32) -> (Vec2, f32) {
+        (
+            self.prev_pos.lerp(self.pos, alpha),
+            self.prev_rotation + (self.rotation - self.prev_rotation) * alpha,
+        )
     }
 
-    pub fn update(&mut self, delta_time: &Duration) {
-        let delta_time = delta_time.as_millis() as f32;
+    /// step the aircraft, `bounds` is the size of the area it flies in
+    pub fn update(&mut self, delta_time: &Duration, bounds: Vec2) {
+        // make the aircraft respond to its input
+        let input = std::mem::take(&mut self.input);
+        self.apply_input(&input, delta_time);
+        self.input = input;
+
+        let delta_time = delta_time.as_secs_f32();
+
+        self.spawn_protection = (self.spawn_protection - delta_time).max(0.0);
+
+        self.prev_pos = self.pos;
+        self.prev_rotation = self.rotation;
 
         // update speed
         self.update_speed(delta_time);
@@ -134,17 +263,9 @@ impl Player {
         self.update_turn_rate();
 
         // update velocity
-        self.apply_velocity(delta_time);
-
-        // handle turning
-        match self.state {
-            PlayerState::TurningLeft => {
-                self.rotation -= self.turn_rate;
-            }
-            PlayerState::TurningRight => {
-                self.rotation += self.turn_rate;
-            }
-            _ => {}
-        }
+        self.apply_velocity(delta_time, bounds);
+
+        // handle turning (turn rate is in radians per second)
+        self.rotation += self.turn_rate * self.roll * delta_time;
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,327 @@
+use std::time::Duration;
+
+use macroquad::{math::{vec2, Vec2}, rand::gen_range};
+use serde::{Deserialize, Serialize};
+
+use crate::world::{Hit, Weapon, World};
+
+// how long kills stay in the kill feed, in seconds
+const KILL_FEED_DURATION: f32 = 6.0;
+const KILL_FEED_LENGTH: usize = 5;
+// seconds the results are shown for before the next match starts
+const RESULTS_DURATION: f32 = 15.0;
+// respawns try to keep at least this far from anyone else, in pixels
+const SPAWN_CLEARANCE: f32 = 300.0;
+const SPAWN_ATTEMPTS: u32 = 10;
+
+/// How a PvP match is played, limits of 0 mean there is no limit
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MatchRules {
+    // kills needed to win the match
+    pub score_limit: u32,
+    // seconds the match lasts, the most kills when it runs out wins
+    pub time_limit: f32,
+    // seconds a destroyed aircraft waits before respawning
+    pub respawn_delay: f32,
+    // seconds an aircraft can't be damaged after respawning
+    pub spawn_protection: f32,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            score_limit: 10,
+            time_limit: 300.0,
+            respawn_delay: 3.0,
+            spawn_protection: 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Score {
+    pub id: u32,
+    pub name: String,
+    pub kills: u32,
+    pub deaths: u32,
+    // seconds until a destroyed aircraft respawns
+    pub respawn_in: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct Kill {
+    pub killer: u32,
+    pub victim: u32,
+    pub weapon: Weapon,
+    // seconds since the kill
+    pub age: f32,
+}
+
+/// What clients need to show the match, sent with every snapshot
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MatchStatus {
+    // counts up every match so clients can tell when a new one has started
+    pub number: u32,
+    pub score_limit: u32,
+    // seconds left, if the match has a time limit
+    pub time_left: Option<f32>,
+    // best first
+    pub scores: Vec<Score>,
+    pub kill_feed: Vec<Kill>,
+    // seconds until the next match once this one is over
+    pub next_match_in: Option<f32>,
+}
+
+impl MatchStatus {
+    pub fn score(&self, id: u32) -> Option<&Score> {
+        self.scores.iter().find(|s| s.id == id)
+    }
+
+    pub fn name(&self, id: u32) -> &str {
+        self.score(id).map(|s| s.name.as_str()).unwrap_or("???")
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_match_in.is_some()
+    }
+}
+
+/// Keeps score in a free for all match and respawns aircraft as they are destroyed
+#[derive(Debug, Clone)]
+pub struct Match {
+    rules: MatchRules,
+    number: u32,
+    elapsed: f32,
+    scores: Vec<Score>,
+    kill_feed: Vec<Kill>,
+    // seconds until the next match, set once the match is over
+    next_match_in: Option<f32>,
+}
+
+impl Match {
+    pub fn new(rules: MatchRules) -> Self {
+        Self {
+            rules,
+            number: 1,
+            elapsed: 0.0,
+            scores: Vec::new(),
+            kill_feed: Vec::new(),
+            next_match_in: None,
+        }
+    }
+
+    pub fn rules(&self) -> &MatchRules {
+        &self.rules
+    }
+
+    /// add a player to the scoreboard, they start protected like any respawn
+    pub fn join(&mut self, world: &mut World, id: u32, name: String) {
+        self.scores.push(Score {
+            id,
+            name,
+            kills: 0,
+            deaths: 0,
+            respawn_in: None,
+        });
+        self.respawn(world, id);
+    }
+
+    pub fn leave(&mut self, id: u32) {
+        self.scores.retain(|s| s.id != id);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_match_in.is_some()
+    }
+
+    pub fn status(&self) -> MatchStatus {
+        let mut scores = self.scores.clone();
+        scores.sort_by(|a, b| b.kills.cmp(&a.kills).then(a.deaths.cmp(&b.deaths)));
+
+        MatchStatus {
+            number: self.number,
+            score_limit: self.rules.score_limit,
+            time_left: (self.rules.time_limit > 0.0).then(|| (self.rules.time_limit - self.elapsed).max(0.0)),
+            scores,
+            kill_feed: self.kill_feed.clone(),
+            next_match_in: self.next_match_in,
+        }
+    }
+
+    /// score the hits from the last world step and handle respawns and the end of the match
+    pub fn update(&mut self, world: &mut World, hits: &[Hit], delta_time: &Duration) {
+        let dt = delta_time.as_secs_f32();
+
+        for kill in &mut self.kill_feed {
+            kill.age += dt;
+        }
+        self.kill_feed.retain(|k| k.age < KILL_FEED_DURATION);
+
+        if let Some(remaining) = self.next_match_in {
+            let remaining = remaining - dt;
+            if remaining <= 0.0 {
+                self.restart(world);
+            } else {
+                self.next_match_in = Some(remaining);
+            }
+            return;
+        }
+
+        self.elapsed += dt;
+        self.score_hits(world, hits);
+
+        // bring destroyed aircraft back once they have waited long enough
+        let mut respawns = Vec::new();
+        for score in &mut self.scores {
+            if let Some(remaining) = score.respawn_in {
+                let remaining = remaining - dt;
+                if remaining <= 0.0 {
+                    score.respawn_in = None;
+                    respawns.push(score.id);
+                } else {
+                    score.respawn_in = Some(remaining);
+                }
+            }
+        }
+        for id in respawns {
+            self.respawn(world, id);
+        }
+
+        let score_reached = self.rules.score_limit > 0 && self.scores.iter().any(|s| s.kills >= self.rules.score_limit);
+        let time_up = self.rules.time_limit > 0.0 && self.elapsed >= self.rules.time_limit;
+        if score_reached || time_up {
+            self.next_match_in = Some(RESULTS_DURATION);
+        }
+    }
+
+    fn score_hits(&mut self, world: &World, hits: &[Hit]) {
+        // only the hit that destroyed the aircraft counts
+        for hit in hits.iter().filter(|h| h.killed) {
+            let Some(victim) = world.player(hit.target) else {
+                continue;
+            };
+
+            if let Some(score) = self.scores.iter_mut().find(|s| s.id == victim.id) {
+                score.deaths += 1;
+                score.respawn_in = Some(self.rules.respawn_delay);
+            }
+            if let Some(score) = self.scores.iter_mut().find(|s| s.id == hit.owner) {
+                score.kills += 1;
+            }
+
+            self.kill_feed.insert(0, Kill {
+                killer: hit.owner,
+                victim: victim.id,
+                weapon: hit.weapon,
+                age: 0.0,
+            });
+            self.kill_feed.truncate(KILL_FEED_LENGTH);
+        }
+    }
+
+    fn respawn(&mut self, world: &mut World, id: u32) {
+        let pos = spawn_point(world, id);
+        let rotation = gen_range(0.0, std::f32::consts::TAU);
+        world.respawn_player(id, pos, rotation);
+        if let Some(player) = world.player_mut(id) {
+            player.spawn_protection = self.rules.spawn_protection;
+        }
+    }
+
+    fn restart(&mut self, world: &mut World) {
+        self.number += 1;
+        self.elapsed = 0.0;
+        self.kill_feed.clear();
+        self.next_match_in = None;
+        // nothing from the last match should carry over into the next one
+        world.bullets.clear();
+        world.missiles.clear();
+
+        let ids: Vec<u32> = self.scores.iter().map(|s| s.id).collect();
+        for score in &mut self.scores {
+            score.kills = 0;
+            score.deaths = 0;
+            score.respawn_in = None;
+        }
+        for id in ids {
+            self.respawn(world, id);
+        }
+    }
+}
+
+/// a random point away from every other aircraft, or as far as could be found
+fn spawn_point(world: &World, id: u32) -> Vec2 {
+    let bounds = world.bounds;
+    let clearance = |pos: Vec2| {
+        world.players.iter()
+            .filter(|p| p.id != id && p.is_alive())
+            .map(|p| p.pos.distance(pos))
+            .fold(f32::MAX, f32::min)
+    };
+
+    let mut best = bounds / 2.0;
+    let mut best_clearance = -1.0;
+    for _ in 0..SPAWN_ATTEMPTS {
+        let pos = vec2(gen_range(0.1, 0.9) * bounds.x, gen_range(0.1, 0.9) * bounds.y);
+        let distance = clearance(pos);
+        if distance >= SPAWN_CLEARANCE {
+            return pos;
+        }
+        if distance > best_clearance {
+            best = pos;
+            best_clearance = distance;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{aircraft::{Aircraft, AIRCRAFT_DIR}, weapons::gun::Bullet, world::FIRST_FREE_FOR_ALL_TEAM};
+
+    #[test]
+    fn kill_goes_to_the_lethal_hit() {
+        let aircraft = Aircraft::load(&Path::new(AIRCRAFT_DIR).join("f16.json")).unwrap();
+        let mut world = World::new(vec2(1200.0, 800.0));
+        let mut game = Match::new(MatchRules::default());
+        let ids: Vec<u32> = (0..3).map(|i| {
+            let id = world.spawn_player(aircraft.clone(), FIRST_FREE_FOR_ALL_TEAM + i, Vec2::ZERO, 0.0);
+            game.join(&mut world, id, format!("Pilot {}", id));
+            id
+        }).collect();
+        let (first, second, victim) = (ids[0], ids[1], ids[2]);
+
+        // line everyone up so only the victim is in the way of the bullets
+        for (id, pos) in [(first, vec2(100.0, 100.0)), (second, vec2(100.0, 700.0)), (victim, vec2(600.0, 400.0))] {
+            let player = world.player_mut(id).unwrap();
+            player.pos = pos;
+            player.spawn_protection = 0.0;
+        }
+        world.player_mut(victim).unwrap().health = 5;
+
+        // both bullets hit in the same step, the first one doesn't finish the victim off
+        for id in [first, second] {
+            let mut bullet = Bullet::fired_from(world.player(id).unwrap(), 0.0);
+            bullet.pos = vec2(400.0, 400.0);
+            bullet.prev_pos = bullet.pos;
+            bullet.velocity = vec2(24_000.0, 0.0);
+            bullet.damage = 3;
+            world.bullets.push(bullet);
+        }
+
+        let step = Duration::from_secs_f64(1.0 / 60.0);
+        let hits = world.update(&step);
+        assert_eq!(hits.iter().filter(|h| h.target == victim).count(), 2);
+        game.update(&mut world, &hits, &step);
+
+        let status = game.status();
+        assert_eq!(status.score(first).unwrap().kills, 0);
+        assert_eq!(status.score(second).unwrap().kills, 1);
+        assert_eq!(status.score(victim).unwrap().deaths, 1);
+        assert_eq!(status.kill_feed[0].killer, second);
+    }
+}
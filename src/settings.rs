@@ -0,0 +1,244 @@
+use std::{fmt::{self, Display, Formatter}, path::PathBuf, sync::atomic::{AtomicU32, Ordering}};
+
+use macroquad::window::{request_new_screen_size, set_fullscreen};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::{self, ConfigError}, controls, info, logging::{self, LogLevel}, storage, warn};
+
+const SETTINGS_FILE: &str = "settings.dat";
+
+// window sizes offered in the settings screen
+pub const RESOLUTIONS: [(u32, u32); 6] = [(1200, 800), (1280, 720), (1366, 768), (1600, 900), (1920, 1080), (2560, 1440)];
+// 0 is uncapped
+pub const FPS_CAPS: [u32; 6] = [0, 30, 60, 120, 144, 240];
+pub const HUD_SCALE_MIN: f32 = 0.5;
+pub const HUD_SCALE_MAX: f32 = 2.0;
+
+// read every frame by the game loop, so it's kept outside of any one game state
+static FPS_CAP: AtomicU32 = AtomicU32::new(0);
+
+/// How speeds are shown to the player, the simulation always works in m/s
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub enum SpeedUnit {
+    #[default]
+    Knots,
+    Kmh,
+}
+
+impl SpeedUnit {
+    pub fn next(self) -> Self {
+        match self {
+            SpeedUnit::Knots => SpeedUnit::Kmh,
+            SpeedUnit::Kmh => SpeedUnit::Knots,
+        }
+    }
+
+    /// `speed` in m/s converted to this unit
+    pub fn convert(self, speed: f32) -> f32 {
+        match self {
+            SpeedUnit::Knots => speed * 1.94384,
+            SpeedUnit::Kmh => speed * 3.6,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            SpeedUnit::Knots => "kts",
+            SpeedUnit::Kmh => "km/h",
+        }
+    }
+}
+
+impl Display for SpeedUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SpeedUnit::Knots => write!(f, "Knots"),
+            SpeedUnit::Kmh => write!(f, "km/h"),
+        }
+    }
+}
+
+/// How the player turns their aircraft
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub enum SteeringMode {
+    // the roll actions turn the aircraft
+    #[default]
+    Keys,
+    // the aircraft turns towards the mouse cursor, the roll actions still override it
+    MouseAim,
+}
+
+impl SteeringMode {
+    pub fn next(self) -> Self {
+        match self {
+            SteeringMode::Keys => SteeringMode::MouseAim,
+            SteeringMode::MouseAim => SteeringMode::Keys,
+        }
+    }
+}
+
+impl Display for SteeringMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SteeringMode::Keys => write!(f, "Keys"),
+            SteeringMode::MouseAim => write!(f, "Mouse Aim"),
+        }
+    }
+}
+
+/// Everything the player can change outside of the controls, saved next to them
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+// settings added later fall back to their default in files written before them
+#[serde(default)]
+pub struct Settings {
+    // window size when not fullscreen
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+    // only read when the window is created
+    pub vsync: bool,
+    pub fps_cap: u32,
+    // all volumes are 0.0 - 1.0, music and sfx are scaled by master
+    pub master_volume: f32,
+    // there is no music yet, so this isn't shown in the settings screen
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub hud_scale: f32,
+    pub units: SpeedUnit,
+    pub steering: SteeringMode,
+    pub show_fps: bool,
+    // acceleration, turn rate, drag and thrust on the HUD
+    pub show_flight_data: bool,
+    pub debug_output: bool,
+    // keep a copy of the log in the log directory to go with bug reports
+    pub log_to_file: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            width: 1200,
+            height: 800,
+            fullscreen: false,
+            vsync: true,
+            fps_cap: 0,
+            master_volume: 1.0,
+            music_volume: 0.8,
+            sfx_volume: 1.0,
+            hud_scale: 1.0,
+            units: SpeedUnit::Knots,
+            steering: SteeringMode::Keys,
+            show_fps: true,
+            show_flight_data: true,
+            debug_output: logging::DEFAULT_LEVEL == LogLevel::Debug,
+            log_to_file: true,
+        }
+    }
+}
+
+impl Settings {
+    /// load the settings file, falling back to the defaults if it is missing or can't be used
+    pub fn load() -> Self {
+        let path = settings_path();
+
+        let contents = match config::read(&path) {
+            Ok(Some(contents)) => contents,
+            Ok(None) => {
+                info!("No settings.dat found, creating one with the default settings");
+                let settings = Self {
+                    // the steering mode used to be kept with the controls
+                    steering: controls::legacy_steering().unwrap_or_default(),
+                    ..Self::default()
+                };
+                return settings.save_or_warn();
+            }
+            Err(e) => {
+                // the file may be fine and just unreadable right now, so leave it alone
+                warn!("{}, using the default settings", e);
+                return Self::default();
+            }
+        };
+
+        match serde_json::from_str::<Self>(&contents) {
+            Ok(settings) => settings.sanitized(),
+            Err(e) => {
+                warn!("{}, using the default settings", ConfigError::Parse(path.clone(), e.to_string()));
+                match config::back_up_corrupt(&path) {
+                    Ok(backup) => {
+                        info!("Moved the unreadable settings to {}", backup.display());
+                    }
+                    Err(e) => {
+                        warn!("{}", e);
+                        return Self::default();
+                    }
+                }
+                Self::default().save_or_warn()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        // pretty so it can be edited by hand
+        let serialized = serde_json::to_string_pretty(self)
+            .map_err(|e| ConfigError::Serialize(settings_path(), e.to_string()))?;
+        config::write_atomic(&settings_path(), &serialized)
+    }
+
+    fn save_or_warn(self) -> Self {
+        if let Err(e) = self.save() {
+            warn!("{}", e);
+        }
+        self
+    }
+
+    // hand edited files can hold anything, keep it to what the game can actually use
+    fn sanitized(mut self) -> Self {
+        let defaults = Self::default();
+        if self.width == 0 || self.height == 0 {
+            self.width = defaults.width;
+            self.height = defaults.height;
+        }
+        let unit = |v: f32, default: f32| if v.is_finite() { v.clamp(0.0, 1.0) } else { default };
+        self.master_volume = unit(self.master_volume, defaults.master_volume);
+        self.music_volume = unit(self.music_volume, defaults.music_volume);
+        self.sfx_volume = unit(self.sfx_volume, defaults.sfx_volume);
+        self.hud_scale = if self.hud_scale.is_finite() {
+            self.hud_scale.clamp(HUD_SCALE_MIN, HUD_SCALE_MAX)
+        } else {
+            defaults.hud_scale
+        };
+        self
+    }
+
+    /// put everything that can change while the game is running into effect
+    pub fn apply(&self) {
+        set_fullscreen(self.fullscreen);
+        if !self.fullscreen {
+            request_new_screen_size(self.width as f32, self.height as f32);
+        }
+        self.apply_globals();
+    }
+
+    /// the parts of `apply` that don't need a window yet
+    pub fn apply_globals(&self) {
+        FPS_CAP.store(self.fps_cap, Ordering::Relaxed);
+        logging::set_level(if self.debug_output { LogLevel::Debug } else { LogLevel::Info });
+        let log_dir = self.log_to_file.then(storage::log_dir);
+        if let Err(e) = logging::set_file(log_dir) {
+            warn!("{}", e);
+        }
+    }
+
+    pub fn sfx_volume(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+}
+
+/// frames per second the game loop is held to, 0 for as fast as it can go
+pub fn fps_cap() -> u32 {
+    FPS_CAP.load(Ordering::Relaxed)
+}
+
+fn settings_path() -> PathBuf {
+    storage::config_file(SETTINGS_FILE)
+}
@@ -0,0 +1,143 @@
+use std::{env, path::{Path, PathBuf}, sync::OnceLock};
+
+use crate::warn;
+
+const APP_NAME: &str = "merlin";
+// where settings lived before they moved to the platform's directories, checked so they carry over
+pub const LEGACY_DIR: &str = "./data";
+// a file with this name next to the executable turns on portable mode
+const PORTABLE_MARKER: &str = "portable";
+
+static STORAGE: OnceLock<Storage> = OnceLock::new();
+
+/// How the player asked for the storage directories to be chosen
+#[derive(Debug, Clone, Default)]
+pub struct StorageOptions {
+    // use this directory for config instead of the platform's one
+    pub config_dir: Option<PathBuf>,
+    // keep everything next to the executable so the game can be carried around on a usb stick
+    pub portable: bool,
+}
+
+impl StorageOptions {
+    /// read `MERLIN_CONFIG_DIR` and `MERLIN_PORTABLE`
+    pub fn from_env() -> Self {
+        Self {
+            config_dir: env_path("MERLIN_CONFIG_DIR"),
+            portable: env::var_os("MERLIN_PORTABLE").is_some_and(|v| !v.is_empty() && v != "0"),
+        }
+    }
+}
+
+/// The directories the game keeps the player's files in
+#[derive(Debug, Clone)]
+pub struct Storage {
+    config: PathBuf,
+    saves: PathBuf,
+    logs: PathBuf,
+}
+
+impl Storage {
+    pub fn resolve(options: &StorageOptions) -> Self {
+        let portable = options.portable || executable_dir().is_some_and(|dir| dir.join(PORTABLE_MARKER).exists());
+        let mut storage = match platform_dirs() {
+            Some(storage) if !portable => storage,
+            // without a home directory there's nowhere better than beside the game
+            _ => portable_dirs(),
+        };
+        if let Some(dir) = &options.config_dir {
+            storage.config = dir.clone();
+        }
+        storage
+    }
+}
+
+/// choose the storage directories, must be called before anything is loaded or saved to take effect
+pub fn init(options: &StorageOptions) {
+    if STORAGE.set(Storage::resolve(options)).is_err() {
+        warn!("Storage directories were already chosen, ignoring {:?}", options);
+    }
+}
+
+fn storage() -> &'static Storage {
+    STORAGE.get_or_init(|| Storage::resolve(&StorageOptions::from_env()))
+}
+
+/// settings and controls
+pub fn config_dir() -> &'static Path {
+    &storage().config
+}
+
+/// progress and anything else the game saves
+pub fn save_dir() -> &'static Path {
+    &storage().saves
+}
+
+pub fn log_dir() -> &'static Path {
+    &storage().logs
+}
+
+pub fn config_file(name: &str) -> PathBuf {
+    config_dir().join(name)
+}
+
+pub fn save_file(name: &str) -> PathBuf {
+    save_dir().join(name)
+}
+
+fn env_path(name: &str) -> Option<PathBuf> {
+    env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from)
+}
+
+fn executable_dir() -> Option<PathBuf> {
+    env::current_exe().ok()?.parent().map(Path::to_path_buf)
+}
+
+fn portable_dirs() -> Storage {
+    let root = executable_dir().unwrap_or_else(|| PathBuf::from(".")).join("data");
+    Storage {
+        saves: root.join("saves"),
+        logs: root.join("logs"),
+        config: root,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn platform_dirs() -> Option<Storage> {
+    let roaming = env_path("APPDATA")?.join(APP_NAME);
+    let local = env_path("LOCALAPPDATA").map(|dir| dir.join(APP_NAME)).unwrap_or_else(|| roaming.clone());
+    Some(Storage {
+        config: roaming.clone(),
+        saves: roaming.join("saves"),
+        logs: local.join("logs"),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn platform_dirs() -> Option<Storage> {
+    let home = env_path("HOME")?;
+    let support = home.join("Library/Application Support").join(APP_NAME);
+    Some(Storage {
+        config: support.clone(),
+        saves: support.join("saves"),
+        logs: home.join("Library/Logs").join(APP_NAME),
+    })
+}
+
+// everything else follows the XDG base directory spec
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn platform_dirs() -> Option<Storage> {
+    let home = env_path("HOME");
+    // the spec says relative paths are invalid and should be ignored
+    let xdg = |var: &str, fallback: &str| {
+        env_path(var)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| home.as_ref().map(|home| home.join(fallback)))
+            .map(|dir| dir.join(APP_NAME))
+    };
+    Some(Storage {
+        config: xdg("XDG_CONFIG_HOME", ".config")?,
+        saves: xdg("XDG_DATA_HOME", ".local/share")?.join("saves"),
+        logs: xdg("XDG_STATE_HOME", ".local/state")?.join("logs"),
+    })
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use crate::SIMULATION_TIMESTEP;
+
+// frames longer than this (i.e. dragging the window) are clamped so the simulation doesn't spiral trying to catch up
+const MAX_FRAME_TIME: Duration = Duration::from_millis(250);
+
+/// Turns frames of any length into whole simulation steps, so the simulation runs the same at every framerate
+#[derive(Debug, Clone, Default)]
+pub struct FixedTimestep {
+    // simulation time that has passed but not been stepped yet
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add a frame's worth of time, returning how many simulation steps are now due
+    pub fn advance(&mut self, frame_time: Duration) -> u32 {
+        self.accumulator += frame_time.min(MAX_FRAME_TIME);
+
+        let mut steps = 0;
+        while self.accumulator >= SIMULATION_TIMESTEP {
+            self.accumulator -= SIMULATION_TIMESTEP;
+            steps += 1;
+        }
+        steps
+    }
+
+    /// how far between simulation steps the frame is rendered, from 0.0 to 1.0
+    pub fn interpolation(&self) -> f32 {
+        self.accumulator.as_secs_f32() / SIMULATION_TIMESTEP.as_secs_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use macroquad::math::vec2;
+
+    use super::*;
+    use crate::{aircraft::{Aircraft, AIRCRAFT_DIR}, controls::Action, player::{Player, PlayerInput}};
+
+    /// fly the same inputs for `steps` simulation steps, with the frames `frame_time` apart
+    fn fly(frame_time: Duration, steps: u32) -> Player {
+        let aircraft = Aircraft::load(&Path::new(AIRCRAFT_DIR).join("f16.json")).unwrap();
+        let mut player = Player::new(1, 0, aircraft);
+        player.pos = vec2(600.0, 400.0);
+
+        let mut timestep = FixedTimestep::new();
+        let mut stepped = 0;
+        while stepped < steps {
+            for _ in 0..timestep.advance(frame_time) {
+                if stepped == steps {
+                    break;
+                }
+                // pull a hard turn with the afterburner lit, then ease off and brake
+                player.input = if stepped < steps / 2 {
+                    PlayerInput { roll: 0.8, throttle_target: 110.0, actions: Vec::new() }
+                } else {
+                    PlayerInput { roll: -0.3, throttle_target: 40.0, actions: vec![Action::Airbrake] }
+                };
+                player.update(&SIMULATION_TIMESTEP, vec2(1200.0, 800.0));
+                stepped += 1;
+            }
+        }
+        player
+    }
+
+    #[test]
+    fn framerate_does_not_change_the_flight() {
+        let slow = fly(Duration::from_secs_f64(1.0 / 30.0), 240);
+        let fast = fly(Duration::from_secs_f64(1.0 / 144.0), 240);
+
+        assert_eq!(slow.pos, fast.pos);
+        assert_eq!(slow.rotation, fast.rotation);
+        assert_eq!(slow.speed, fast.speed);
+        assert_eq!(slow.throttle_percent, fast.throttle_percent);
+    }
+
+    #[test]
+    fn long_frames_are_clamped() {
+        let mut timestep = FixedTimestep::new();
+        assert_eq!(timestep.advance(Duration::from_secs(5)), 15);
+        assert!(timestep.interpolation() < 1.0);
+    }
+}
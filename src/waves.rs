@@ -0,0 +1,349 @@
+use std::{path::Path, time::Duration};
+
+use macroquad::{math::{vec2, Vec2}, rand::gen_range};
+use serde::{Deserialize, Serialize};
+
+use crate::{ai::AiPilot, aircraft::Aircraft, boss::BossDef, math, world::{World, DRONE_TEAM}};
+
+pub const WAVES_PATH: &str = "./data/waves.json";
+
+// how far outside the edge of the screen drones spawn
+const SPAWN_MARGIN: f32 = 20.0;
+
+fn default_multiplier() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Wave {
+    // id of the aircraft the drones fly
+    pub aircraft: String,
+    pub count: u32,
+    // seconds between each drone spawning
+    pub spawn_interval: f32,
+    #[serde(default = "default_multiplier")]
+    pub health_multiplier: f32,
+    #[serde(default = "default_multiplier")]
+    pub damage_multiplier: f32,
+}
+
+/// A boss that joins the drones on a specific wave
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BossSpawn {
+    pub wave: u32,
+    // id of the boss definition
+    pub boss: String,
+}
+
+/// How waves keep getting harder once the table runs out
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Escalation {
+    // extra drones per wave past the end of the table
+    pub extra_count: u32,
+    // added to the health and damage multipliers per wave past the end of the table
+    pub multiplier_step: f32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WaveTable {
+    // seconds between a wave being cleared and the next one starting
+    pub breather: f32,
+    pub waves: Vec<Wave>,
+    #[serde(default)]
+    pub bosses: Vec<BossSpawn>,
+    #[serde(default)]
+    pub escalation: Escalation,
+}
+
+impl WaveTable {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let table: WaveTable = serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+
+        if table.waves.is_empty() {
+            return Err(format!("{} has no waves", path.display()));
+        }
+        Ok(table)
+    }
+
+    /// the wave for a 1-based wave number, escalating the last wave in the table once past the end
+    pub fn wave(&self, number: u32) -> Wave {
+        let index = (number.max(1) - 1) as usize;
+        if let Some(wave) = self.waves.get(index) {
+            return wave.clone();
+        }
+
+        let mut wave = self.waves.last().cloned().expect("wave table is never empty");
+        let past_end = (index + 1 - self.waves.len()) as u32;
+        wave.count += self.escalation.extra_count * past_end;
+        wave.health_multiplier += self.escalation.multiplier_step * past_end as f32;
+        wave.damage_multiplier += self.escalation.multiplier_step * past_end as f32;
+        wave
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum WaveState {
+    // waiting before the next wave, with the seconds left
+    Breather(f32),
+    // drones are still being spawned
+    Spawning,
+    // everything has spawned, waiting for the drones and bosses to be destroyed
+    Fighting,
+}
+
+/// What the HUD shows about the waves, small enough to send to network clients every step
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct WaveStatus {
+    pub number: u32,
+    pub state: WaveState,
+    pub remaining: u32,
+}
+
+/// Spawns escalating waves of drones and tracks when each wave has been cleared
+#[derive(Debug, Clone)]
+pub struct WaveDirector {
+    table: WaveTable,
+    roster: Vec<Aircraft>,
+    boss_roster: Vec<BossDef>,
+
+    wave_number: u32,
+    state: WaveState,
+    wave: Option<Wave>,
+    left_to_spawn: u32,
+    spawn_timer: f32,
+    // ids of the drones and bosses from the current wave that are still in the world
+    drones: Vec<u32>,
+    bosses: Vec<u32>,
+}
+
+impl WaveDirector {
+    /// `roster` is every aircraft that drones can fly, `boss_roster` every boss that can be spawned
+    pub fn new(table: WaveTable, roster: &[Aircraft], boss_roster: &[BossDef]) -> Result<Self, String> {
+        // make sure every wave can actually be spawned before the game starts
+        for wave in &table.waves {
+            if !roster.iter().any(|a| a.id == wave.aircraft) {
+                return Err(format!("Wave table uses unknown aircraft '{}'", wave.aircraft));
+            }
+        }
+        for spawn in &table.bosses {
+            if !boss_roster.iter().any(|b| b.id == spawn.boss) {
+                return Err(format!("Wave table uses unknown boss '{}'", spawn.boss));
+            }
+        }
+
+        let breather = table.breather;
+        Ok(Self {
+            table,
+            roster: roster.to_vec(),
+            boss_roster: boss_roster.to_vec(),
+
+            wave_number: 0,
+            state: WaveState::Breather(breather),
+            wave: None,
+            left_to_spawn: 0,
+            spawn_timer: 0.0,
+            drones: Vec::new(),
+            bosses: Vec::new(),
+        })
+    }
+
+    /// the current (or upcoming, during a breather) wave number, starting at 1
+    pub fn wave_number(&self) -> u32 {
+        match self.state {
+            WaveState::Breather(_) => self.wave_number + 1,
+            _ => self.wave_number,
+        }
+    }
+
+    pub fn state(&self) -> WaveState {
+        self.state
+    }
+
+    /// how many drones and bosses from the current wave are alive or yet to spawn
+    pub fn remaining(&self) -> u32 {
+        (self.drones.len() + self.bosses.len()) as u32 + self.left_to_spawn
+    }
+
+    pub fn status(&self) -> WaveStatus {
+        WaveStatus {
+            number: self.wave_number(),
+            state: self.state,
+            remaining: self.remaining(),
+        }
+    }
+
+    pub fn update(&mut self, world: &mut World, delta_time: &Duration) {
+        let dt = delta_time.as_secs_f32();
+
+        // clear out destroyed drones and bosses
+        let dead_drones = world.players.iter()
+            .filter(|p| !p.is_alive() && self.drones.contains(&p.id))
+            .map(|p| p.id);
+        let dead_bosses = world.bosses.iter()
+            .filter(|b| !b.is_alive() && self.bosses.contains(&b.id))
+            .map(|b| b.id);
+        let dead: Vec<u32> = dead_drones.chain(dead_bosses).collect();
+        world.despawn(&dead);
+        self.drones.retain(|id| !dead.contains(id));
+        self.bosses.retain(|id| !dead.contains(id));
+
+        match self.state {
+            WaveState::Breather(remaining) => {
+                let remaining = remaining - dt;
+                if remaining > 0.0 {
+                    self.state = WaveState::Breather(remaining);
+                    return;
+                }
+
+                self.wave_number += 1;
+                let wave = self.table.wave(self.wave_number);
+                self.left_to_spawn = wave.count;
+                self.spawn_timer = 0.0;
+                self.wave = Some(wave);
+                self.state = WaveState::Spawning;
+
+                let bosses: Vec<String> = self.table.bosses.iter()
+                    .filter(|spawn| spawn.wave == self.wave_number)
+                    .map(|spawn| spawn.boss.clone())
+                    .collect();
+                for boss in bosses {
+                    self.spawn_boss(world, &boss);
+                }
+            }
+            WaveState::Spawning => {
+                // a wave can be all bosses, with no drones to spawn
+                if self.left_to_spawn == 0 {
+                    self.state = WaveState::Fighting;
+                    return;
+                }
+
+                self.spawn_timer -= dt;
+                if self.spawn_timer > 0.0 {
+                    return;
+                }
+
+                if let Some(wave) = self.wave.clone() {
+                    self.spawn_drone(world, &wave);
+                    self.spawn_timer = wave.spawn_interval;
+                }
+                self.left_to_spawn = self.left_to_spawn.saturating_sub(1);
+                if self.left_to_spawn == 0 {
+                    self.state = WaveState::Fighting;
+                }
+            }
+            WaveState::Fighting => {
+                if self.drones.is_empty() && self.bosses.is_empty() {
+                    self.state = WaveState::Breather(self.table.breather);
+                }
+            }
+        }
+    }
+
+    fn spawn_drone(&mut self, world: &mut World, wave: &Wave) {
+        let Some(aircraft) = self.roster.iter().find(|a| a.id == wave.aircraft) else {
+            return;
+        };
+
+        // scale the airframe to the wave's difficulty
+        let mut aircraft = aircraft.clone();
+        aircraft.max_health = (aircraft.max_health as f32 * wave.health_multiplier).round() as u32;
+        aircraft.bullet_damage = (aircraft.bullet_damage as f32 * wave.damage_multiplier).round() as u32;
+        aircraft.missile.damage = (aircraft.missile.damage as f32 * wave.damage_multiplier).round() as u32;
+
+        // come in from a random edge, pointed at the middle of the screen
+        let pos = random_edge_point(world.bounds);
+        let rotation = math::heading_to(pos, world.bounds / 2.0);
+
+        let id = world.spawn_player(aircraft, DRONE_TEAM, pos, rotation);
+        if let Some(drone) = world.player_mut(id) {
+            drone.pilot = Some(AiPilot::default());
+        }
+        self.drones.push(id);
+    }
+
+    fn spawn_boss(&mut self, world: &mut World, id: &str) {
+        let Some(def) = self.boss_roster.iter().find(|b| b.id == id) else {
+            return;
+        };
+
+        // bosses make their entrance the same way drones do
+        let pos = random_edge_point(world.bounds);
+        let rotation = math::heading_to(pos, world.bounds / 2.0);
+        let id = world.spawn_boss(def.clone(), DRONE_TEAM, pos, rotation);
+        self.bosses.push(id);
+    }
+}
+
+fn random_edge_point(bounds: Vec2) -> Vec2 {
+    match gen_range(0, 4) {
+        0 => vec2(gen_range(0.0, bounds.x), -SPAWN_MARGIN),
+        1 => vec2(gen_range(0.0, bounds.x), bounds.y + SPAWN_MARGIN),
+        2 => vec2(-SPAWN_MARGIN, gen_range(0.0, bounds.y)),
+        _ => vec2(bounds.x + SPAWN_MARGIN, gen_range(0.0, bounds.y)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::aircraft::AIRCRAFT_DIR;
+
+    fn table(count: u32) -> WaveTable {
+        WaveTable {
+            breather: 0.0,
+            waves: vec![Wave {
+                aircraft: "f16".to_string(),
+                count,
+                spawn_interval: 1.0,
+                health_multiplier: 1.0,
+                damage_multiplier: 1.0,
+            }],
+            bosses: Vec::new(),
+            escalation: Escalation::default(),
+        }
+    }
+
+    #[test]
+    fn waves_come_from_the_table() {
+        let mut table = table(3);
+        table.waves.push(Wave { count: 5, ..table.waves[0].clone() });
+
+        assert_eq!(table.wave(0).count, 3);
+        assert_eq!(table.wave(1).count, 3);
+        assert_eq!(table.wave(2).count, 5);
+    }
+
+    #[test]
+    fn waves_escalate_past_the_end_of_the_table() {
+        let mut table = table(4);
+        table.escalation = Escalation { extra_count: 2, multiplier_step: 0.5 };
+
+        let wave = table.wave(1);
+        assert_eq!((wave.count, wave.health_multiplier), (4, 1.0));
+        let wave = table.wave(2);
+        assert_eq!((wave.count, wave.health_multiplier, wave.damage_multiplier), (6, 1.5, 1.5));
+        let wave = table.wave(4);
+        assert_eq!((wave.count, wave.health_multiplier, wave.damage_multiplier), (10, 2.5, 2.5));
+    }
+
+    #[test]
+    fn empty_wave_spawns_no_drones() {
+        let roster = [Aircraft::load(&Path::new(AIRCRAFT_DIR).join("f16.json")).unwrap()];
+        let mut director = WaveDirector::new(table(0), &roster, &[]).unwrap();
+        let mut world = World::new(vec2(1200.0, 800.0));
+
+        let step = Duration::from_secs_f64(1.0 / 60.0);
+        for _ in 0..3 {
+            director.update(&mut world, &step);
+        }
+
+        assert!(world.players.is_empty());
+        assert_eq!(director.remaining(), 0);
+    }
+}
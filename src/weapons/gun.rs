@@ -0,0 +1,96 @@
+use macroquad::math::Vec2;
+
+use crate::{aircraft::Aircraft, player::{Player, PIXELS_PER_METER}};
+
+#[derive(Debug, Clone)]
+pub struct Gun {
+    pub ammo: u32,
+    fire_interval: f32, // seconds between rounds
+    cooldown: f32, // seconds until the next round can be fired
+}
+
+impl Gun {
+    pub fn new(aircraft: &Aircraft) -> Self {
+        Self {
+            ammo: aircraft.gun_ammo,
+            fire_interval: aircraft.bullet_fire_rate as f32 / 1000.0,
+            cooldown: 0.0,
+        }
+    }
+
+    /// advance the gun by one simulation step, returning how many rounds were fired.
+    /// fast firing guns can fire more than one round per step
+    pub fn update(&mut self, trigger: bool, delta_time: f32) -> u32 {
+        self.cooldown -= delta_time;
+
+        let mut shots = 0;
+        if trigger {
+            while self.cooldown <= 0.0 && self.ammo > 0 {
+                self.ammo -= 1;
+                self.cooldown += self.fire_interval;
+                shots += 1;
+            }
+        }
+
+        // don't bank up time to fire a burst when the trigger is pulled again
+        if self.cooldown < 0.0 {
+            self.cooldown = 0.0;
+        }
+
+        shots
+    }
+
+    pub fn fire_interval(&self) -> f32 {
+        self.fire_interval
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Bullet {
+    // id of the player that fired the bullet, credited with any hit
+    pub owner: u32,
+    // team of the player that fired the bullet, so it can't hit friendly aircraft
+    pub team: u32,
+    pub pos: Vec2,
+    pub prev_pos: Vec2,
+    pub velocity: Vec2, // in pixels per second
+    pub lifetime: f32, // seconds until the bullet expires
+    pub damage: u32,
+}
+
+impl Bullet {
+    /// fire a bullet from the player's nose, inheriting the aircraft's velocity.
+    /// `age` is how long ago in the current step the round left the barrel
+    pub fn fired_from(player: &Player, age: f32) -> Self {
+        let aircraft = &player.aircraft;
+        let muzzle = player.direction() * aircraft.bullet_speed * PIXELS_PER_METER;
+        let velocity = player.velocity() + muzzle;
+        let pos = player.nose() + velocity * age;
+
+        Self {
+            owner: player.id,
+            team: player.team,
+            pos,
+            prev_pos: pos,
+            velocity,
+            lifetime: aircraft.bullet_range / aircraft.bullet_speed - age,
+            damage: aircraft.bullet_damage,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.prev_pos = self.pos;
+        self.pos += self.velocity * delta_time;
+        self.lifetime -= delta_time;
+    }
+
+    pub fn is_expired(&self, bounds: Vec2) -> bool {
+        self.lifetime <= 0.0
+            || self.pos.x < 0.0 || self.pos.x > bounds.x
+            || self.pos.y < 0.0 || self.pos.y > bounds.y
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.velocity.x.atan2(-self.velocity.y)
+    }
+}
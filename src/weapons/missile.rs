@@ -0,0 +1,200 @@
+use macroquad::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::{collision::Hitbox, math, player::{Player, PIXELS_PER_METER}, world::World};
+
+/// Performance of the missiles an aircraft carries, defaults to a generic short range heat seeker
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MissileStats {
+    pub thrust: f32, // acceleration while the motor burns, in m/s^2
+    pub burn_time: f32, // in seconds
+    pub drag: f32, // deceleration once the motor burns out, in m/s^2
+    pub max_speed: f32, // in m/s
+    pub turn_rate: f32, // in rad/s
+    pub lifetime: f32, // in seconds
+    pub fuse_radius: f32, // in pixels, from the edge of the target's hitbox
+    pub damage: u32,
+
+    pub seeker_range: f32, // in m
+    pub seeker_cone: f32, // half angle in rad
+    pub lock_time: f32, // how long a target has to stay in the seeker cone to lock, in seconds
+}
+
+impl Default for MissileStats {
+    fn default() -> Self {
+        Self {
+            thrust: 250.0,
+            burn_time: 2.5,
+            drag: 40.0,
+            max_speed: 900.0,
+            turn_rate: 4.0,
+            lifetime: 8.0,
+            fuse_radius: 20.0,
+            damage: 60,
+
+            seeker_range: 450.0,
+            seeker_cone: 0.5,
+            lock_time: 1.0,
+        }
+    }
+}
+
+/// Tracks what an aircraft's missile seeker is looking at
+#[derive(Debug, Clone, Default)]
+pub struct Seeker {
+    // the target currently in the seeker cone, and for how long
+    pub candidate: Option<u32>,
+    pub dwell: f32,
+    pub locked: Option<u32>,
+}
+
+impl Seeker {
+    /// advance the seeker with the best target in its cone this step
+    pub fn update(&mut self, candidate: Option<u32>, lock_time: f32, delta_time: f32) {
+        if candidate.is_some() && candidate == self.candidate {
+            self.dwell += delta_time;
+        } else {
+            self.candidate = candidate;
+            self.dwell = 0.0;
+        }
+
+        self.locked = if self.candidate.is_some() && self.dwell >= lock_time {
+            self.candidate
+        } else {
+            None
+        };
+    }
+
+    /// the best target for `player`'s seeker: the one closest to the nose inside the cone and in range
+    pub fn find_target(player: &Player, world: &World) -> Option<u32> {
+        let stats = &player.aircraft.missile;
+        let range = stats.seeker_range * PIXELS_PER_METER;
+
+        world.hostile_targets(player.team)
+            .filter_map(|(id, pos)| {
+                let distance = pos.distance(player.pos);
+                if distance > range || distance == 0.0 {
+                    return None;
+                }
+                let angle = math::angle_difference(player.rotation, math::heading_to(player.pos, pos)).abs();
+                (angle <= stats.seeker_cone).then_some((id, angle))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id)
+    }
+}
+
+/// The missiles an aircraft has left, fired once per trigger press
+#[derive(Debug, Clone)]
+pub struct Launcher {
+    pub missiles: u32,
+    trigger_held: bool,
+}
+
+impl Launcher {
+    pub fn new(missiles: u32) -> Self {
+        Self {
+            missiles,
+            trigger_held: false,
+        }
+    }
+
+    /// returns true if a missile should be launched this step
+    pub fn update(&mut self, trigger: bool) -> bool {
+        let pressed = trigger && !self.trigger_held;
+        self.trigger_held = trigger;
+
+        if pressed && self.missiles > 0 {
+            self.missiles -= 1;
+            return true;
+        }
+        false
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Missile {
+    pub owner: u32,
+    pub team: u32,
+    // guided missiles steer towards their target, unguided ones fly straight
+    pub target: Option<u32>,
+
+    pub pos: Vec2,
+    pub prev_pos: Vec2,
+    pub rotation: f32,
+    pub prev_rotation: f32,
+    pub speed: f32, // in m/s
+
+    pub burn_time: f32, // remaining motor burn in seconds
+    pub lifetime: f32,
+    pub stats: MissileStats,
+}
+
+impl Missile {
+    /// launch a missile from the player's nose at the aircraft's speed
+    pub fn fired_from(player: &Player, target: Option<u32>) -> Self {
+        Self::launch(player.id, player.team, player.nose(), player.rotation, player.speed, target, player.aircraft.missile.clone())
+    }
+
+    pub fn launch(owner: u32, team: u32, pos: Vec2, rotation: f32, speed: f32, target: Option<u32>, stats: MissileStats) -> Self {
+        Self {
+            owner,
+            team,
+            target,
+
+            pos,
+            prev_pos: pos,
+            rotation,
+            prev_rotation: rotation,
+            speed,
+
+            burn_time: stats.burn_time,
+            lifetime: stats.lifetime,
+            stats,
+        }
+    }
+
+    pub fn direction(&self) -> Vec2 {
+        math::direction(self.rotation)
+    }
+
+    /// step the missile, steering towards `target_pos` if the target is still around
+    pub fn update(&mut self, target_pos: Option<Vec2>, delta_time: f32) {
+        self.prev_pos = self.pos;
+        self.prev_rotation = self.rotation;
+
+        // motor
+        if self.burn_time > 0.0 {
+            self.burn_time -= delta_time;
+            self.speed += self.stats.thrust * delta_time;
+        } else {
+            self.speed -= self.stats.drag * delta_time;
+        }
+        self.speed = self.speed.clamp(0.0, self.stats.max_speed);
+
+        // guidance, limited by the missile's turn rate
+        if let Some(target_pos) = target_pos {
+            let turn = math::angle_difference(self.rotation, math::heading_to(self.pos, target_pos));
+            let max_turn = self.stats.turn_rate * delta_time;
+            self.rotation += turn.clamp(-max_turn, max_turn);
+        }
+
+        self.pos += self.direction() * self.speed * PIXELS_PER_METER * delta_time;
+        self.lifetime -= delta_time;
+    }
+
+    /// whether the missile is close enough to `hitbox` to detonate
+    pub fn in_fuse_range(&self, hitbox: &Hitbox) -> bool {
+        let reach = hitbox.radius + self.stats.fuse_radius;
+        // check the whole path travelled this step so fast missiles can't skip past the fuse
+        Hitbox::new(hitbox.center, reach).intersects_segment(self.prev_pos, self.pos)
+    }
+
+    pub fn is_expired(&self, bounds: Vec2) -> bool {
+        self.lifetime <= 0.0
+            || self.speed <= 0.0
+            || self.pos.x < 0.0 || self.pos.x > bounds.x
+            || self.pos.y < 0.0 || self.pos.y > bounds.y
+    }
+}
@@ -0,0 +1,2 @@
+pub mod gun;
+pub mod missile;
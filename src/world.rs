@@ -0,0 +1,318 @@
+use std::time::Duration;
+
+use macroquad::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::{aircraft::Aircraft, boss::{Boss, BossDef}, collision::Hitbox, controls::Action, player::Player, weapons::{gun::Bullet, missile::{Missile, Seeker}}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Weapon {
+    Gun,
+    Missile,
+}
+
+/// A projectile hitting something, reported so damage can be applied and kills credited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit {
+    pub owner: u32,
+    pub target: u32,
+    // which component was hit when the target is a boss
+    pub component: Option<usize>,
+    pub damage: u32,
+    pub weapon: Weapon,
+    // whether this hit destroyed the target, only ever true for one hit on it
+    pub killed: bool,
+}
+
+// teams that aircraft can be on, aircraft only damage aircraft on other teams
+pub const PLAYER_TEAM: u32 = 0;
+pub const DRONE_TEAM: u32 = 1;
+// in free for all matches every aircraft gets its own team, starting from this one
+pub const FIRST_FREE_FOR_ALL_TEAM: u32 = 2;
+
+/// Everything that is simulated: aircraft, bosses and the projectiles flying between them
+#[derive(Debug, Clone)]
+pub struct World {
+    pub players: Vec<Player>,
+    pub bosses: Vec<Boss>,
+    pub bullets: Vec<Bullet>,
+    // bullets fired during the last step, network clients are only told about these and fly them themselves
+    pub shots: Vec<Bullet>,
+    pub missiles: Vec<Missile>,
+    // size of the playable area in pixels, aircraft wrap around at its edges
+    pub bounds: Vec2,
+    next_id: u32,
+}
+
+impl World {
+    pub fn new(bounds: Vec2) -> Self {
+        Self {
+            players: Vec::new(),
+            bosses: Vec::new(),
+            bullets: Vec::new(),
+            shots: Vec::new(),
+            missiles: Vec::new(),
+            bounds,
+            next_id: 0,
+        }
+    }
+
+    fn next_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// add a new aircraft to the world, returning its id
+    pub fn spawn_player(&mut self, aircraft: Aircraft, team: u32, pos: Vec2, rotation: f32) -> u32 {
+        let id = self.next_id();
+
+        let mut player = Player::new(id, team, aircraft);
+        player.pos = pos;
+        player.prev_pos = pos;
+        player.rotation = rotation;
+        player.prev_rotation = rotation;
+        self.players.push(player);
+
+        id
+    }
+
+    /// put a destroyed aircraft back into the fight as good as new, keeping its id, team and pilot
+    pub fn respawn_player(&mut self, id: u32, pos: Vec2, rotation: f32) {
+        let Some(player) = self.player_mut(id) else {
+            return;
+        };
+
+        let mut respawned = Player::new(id, player.team, player.aircraft.clone());
+        respawned.pilot = player.pilot.take();
+        respawned.pos = pos;
+        respawned.prev_pos = pos;
+        respawned.rotation = rotation;
+        respawned.prev_rotation = rotation;
+        *player = respawned;
+    }
+
+    /// add a new boss to the world, returning its id
+    pub fn spawn_boss(&mut self, def: BossDef, team: u32, pos: Vec2, rotation: f32) -> u32 {
+        let id = self.next_id();
+        self.bosses.push(Boss::new(id, team, def, pos, rotation));
+        id
+    }
+
+    pub fn boss(&self, id: u32) -> Option<&Boss> {
+        self.bosses.iter().find(|b| b.id == id)
+    }
+
+    /// id and position of everything alive that `team` can shoot at
+    pub fn hostile_targets(&self, team: u32) -> impl Iterator<Item = (u32, Vec2)> + '_ {
+        let players = self.players.iter()
+            .filter(move |p| p.team != team && p.is_alive())
+            .map(|p| (p.id, p.pos));
+        let bosses = self.bosses.iter()
+            .filter(move |b| b.team != team && b.is_alive())
+            .map(|b| (b.id, b.pos));
+        players.chain(bosses)
+    }
+
+    /// position of an aircraft or boss by id, if it is still alive
+    pub fn target_pos(&self, id: u32) -> Option<Vec2> {
+        self.player(id).filter(|p| p.is_alive()).map(|p| p.pos)
+            .or_else(|| self.boss(id).filter(|b| b.is_alive()).map(|b| b.pos))
+    }
+
+    pub fn player(&self, id: u32) -> Option<&Player> {
+        self.players.iter().find(|p| p.id == id)
+    }
+
+    pub fn player_mut(&mut self, id: u32) -> Option<&mut Player> {
+        self.players.iter_mut().find(|p| p.id == id)
+    }
+
+    /// remove aircraft and bosses from the world, along with anything that was targeting them
+    pub fn despawn(&mut self, ids: &[u32]) {
+        self.players.retain(|p| !ids.contains(&p.id));
+        self.bosses.retain(|b| !ids.contains(&b.id));
+        for missile in &mut self.missiles {
+            if missile.target.is_some_and(|t| ids.contains(&t)) {
+                missile.target = None;
+            }
+        }
+    }
+
+    /// step the world, applying and returning every hit that happened
+    pub fn update(&mut self, delta_time: &Duration) -> Vec<Hit> {
+        let dt = delta_time.as_secs_f32();
+        self.shots.clear();
+
+        // let the AI pilots decide what to do, they see the world as it was at the start of the step
+        for i in 0..self.players.len() {
+            if !self.players[i].is_alive() {
+                continue;
+            }
+            if let Some(mut pilot) = self.players[i].pilot.take() {
+                let input = pilot.think(&self.players[i], self, delta_time);
+                self.players[i].input = input;
+                self.players[i].pilot = Some(pilot);
+            }
+        }
+
+        // fly the aircraft and fire their guns
+        for player in self.players.iter_mut().filter(|p| p.is_alive()) {
+            player.update(delta_time, self.bounds);
+
+            let trigger = player.input.holds(Action::FireGun);
+            let shots = player.gun.update(trigger, dt);
+            let interval = player.gun.fire_interval();
+            for shot in 0..shots {
+                // the first round fired this step has been flying the longest
+                let age = (shots - 1 - shot) as f32 * interval;
+                let bullet = Bullet::fired_from(player, age);
+                self.shots.push(bullet.clone());
+                self.bullets.push(bullet);
+            }
+        }
+
+        // fly the bosses and fire their weapons
+        for i in 0..self.bosses.len() {
+            if !self.bosses[i].is_alive() {
+                continue;
+            }
+            let targets: Vec<(u32, Vec2)> = self.players.iter()
+                .filter(|p| p.team != self.bosses[i].team && p.is_alive())
+                .map(|p| (p.id, p.pos))
+                .collect();
+            let (bullets, missiles) = self.bosses[i].update(&targets, self.bounds, dt);
+            self.shots.extend(bullets.iter().cloned());
+            self.bullets.extend(bullets);
+            self.missiles.extend(missiles);
+        }
+
+        // point the seekers at whatever is in front of them
+        for i in 0..self.players.len() {
+            let player = &self.players[i];
+            if !player.is_alive() {
+                continue;
+            }
+            let candidate = Seeker::find_target(player, self);
+            let lock_time = player.aircraft.missile.lock_time;
+            self.players[i].seeker.update(candidate, lock_time, dt);
+        }
+
+        // launch missiles, guided if the seeker has a lock
+        for player in self.players.iter_mut().filter(|p| p.is_alive()) {
+            let trigger = player.input.holds(Action::FireMissile);
+            if player.launcher.update(trigger) {
+                self.missiles.push(Missile::fired_from(player, player.seeker.locked));
+            }
+        }
+
+        let mut hits = Vec::new();
+
+        // move the bullets and check them against everything they passed through this step
+        for bullet in &mut self.bullets {
+            bullet.update(dt);
+
+            let struck = find_struck(&self.players, &self.bosses, bullet.team, |hitbox| {
+                hitbox.intersects_segment(bullet.prev_pos, bullet.pos)
+            });
+            if let Some((target, component)) = struck {
+                let killed = apply_damage(&mut self.players, &mut self.bosses, target, component, bullet.damage);
+                hits.push(Hit {
+                    owner: bullet.owner,
+                    target,
+                    component,
+                    damage: bullet.damage,
+                    weapon: Weapon::Gun,
+                    killed,
+                });
+                // spent bullets are cleaned up below
+                bullet.lifetime = 0.0;
+            }
+        }
+        let bounds = self.bounds;
+        self.bullets.retain(|b| !b.is_expired(bounds));
+
+        // fly the missiles and detonate them when an enemy is inside the proximity fuse
+        for i in 0..self.missiles.len() {
+            let target_pos = self.missiles[i].target.and_then(|id| self.target_pos(id));
+            let missile = &mut self.missiles[i];
+            missile.update(target_pos, dt);
+
+            let struck = find_struck(&self.players, &self.bosses, missile.team, |hitbox| {
+                missile.in_fuse_range(hitbox)
+            });
+            if let Some((target, component)) = struck {
+                let killed = apply_damage(&mut self.players, &mut self.bosses, target, component, missile.stats.damage);
+                hits.push(Hit {
+                    owner: missile.owner,
+                    target,
+                    component,
+                    damage: missile.stats.damage,
+                    weapon: Weapon::Missile,
+                    killed,
+                });
+                missile.lifetime = 0.0;
+            }
+        }
+        self.missiles.retain(|m| !m.is_expired(bounds));
+
+        hits
+    }
+
+    /// fly the bullets without them doing any damage, stopping them at whatever they hit.
+    /// used by network clients, which only hear about bullets when they are fired
+    pub fn advance_bullets(&mut self, delta_time: f32) {
+        for bullet in &mut self.bullets {
+            bullet.update(delta_time);
+
+            let struck = find_struck(&self.players, &self.bosses, bullet.team, |hitbox| {
+                hitbox.intersects_segment(bullet.prev_pos, bullet.pos)
+            });
+            if struck.is_some() {
+                bullet.lifetime = 0.0;
+            }
+        }
+        let bounds = self.bounds;
+        self.bullets.retain(|b| !b.is_expired(bounds));
+    }
+}
+
+/// the first enemy of `team` that `struck` says was hit, as its id and the boss component hit
+fn find_struck(players: &[Player], bosses: &[Boss], team: u32, struck: impl Fn(&Hitbox) -> bool) -> Option<(u32, Option<usize>)> {
+    let player = players.iter()
+        .filter(|p| p.is_alive() && p.team != team)
+        .find(|p| struck(&p.hitbox()));
+    if let Some(player) = player {
+        return Some((player.id, None));
+    }
+
+    bosses.iter()
+        .filter(|b| b.is_alive() && b.team != team)
+        .find_map(|b| {
+            b.hitboxes()
+                .find(|(_, hitbox)| struck(hitbox))
+                .map(|(component, _)| (b.id, Some(component)))
+        })
+}
+
+/// damage an aircraft or boss component, returning whether that destroyed the target
+fn apply_damage(players: &mut [Player], bosses: &mut [Boss], target: u32, component: Option<usize>, damage: u32) -> bool {
+    match component {
+        Some(component) => {
+            let Some(boss) = bosses.iter_mut().find(|b| b.id == target) else {
+                return false;
+            };
+            let was_alive = boss.is_alive();
+            boss.take_damage(component, damage);
+            was_alive && !boss.is_alive()
+        }
+        None => {
+            let Some(player) = players.iter_mut().find(|p| p.id == target) else {
+                return false;
+            };
+            let was_alive = player.is_alive();
+            player.take_damage(damage);
+            was_alive && !player.is_alive()
+        }
+    }
+}